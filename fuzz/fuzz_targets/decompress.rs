@@ -0,0 +1,21 @@
+#![no_main]
+
+use hoteldusk_tools::util::decompress;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, rest)) = data.split_first() else {
+        return;
+    };
+
+    // prepend the magic so both branches get exercised without the fuzzer
+    // having to discover it first
+    let mut input = match selector % 3 {
+        0 => vec![0x12, 0x3D, 0xDA, 0x01],
+        1 => vec![0x30],
+        _ => vec![],
+    };
+    input.extend_from_slice(rest);
+
+    let _ = decompress(&mut input.as_slice());
+});
@@ -0,0 +1,19 @@
+#![no_main]
+
+use hoteldusk_tools::util::{decompress, write_lzss, write_rle};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut compressed = Vec::new();
+    write_lzss(&mut compressed, data).unwrap();
+    assert_eq!(decompress(&mut compressed.as_slice()).unwrap(), data);
+
+    // the rle header only has room for a 16-bit size
+    if data.len() > 0xFFFF {
+        return;
+    }
+
+    compressed.clear();
+    write_rle(&mut compressed, data).unwrap();
+    assert_eq!(decompress(&mut compressed.as_slice()).unwrap(), data);
+});
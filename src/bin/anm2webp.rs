@@ -1,7 +1,11 @@
-use hoteldusk_tools::util::{Color, ReadExt};
+use hoteldusk_tools::{
+    cli,
+    util::{Color, DecompressReader, Expansion, ReadExt, Transparency},
+};
 use std::{
     error::Error,
-    io::{Cursor, Read, Seek},
+    fs::File,
+    io::{BufReader, Cursor, Read, Seek},
     path::Path,
 };
 
@@ -10,9 +14,21 @@ const MTC_WIDTH: usize = 17;
 const MTC_HEIGHT: usize = 33;
 const MTC_SUFFIX: &str = "m_.mtc";
 
+/// options that take a value
+const VALUE_OPTIONS: &[&str] = &["--expansion", "--transparency"];
+
 fn main() -> Result<(), Box<dyn Error>> {
-    let anm_files = std::env::args_os()
-        .skip(1)
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let transparency = cli::value(&args, "--transparency")
+        .map(|name| Transparency::from_name(name).ok_or(format!("unknown transparency {name}")))
+        .transpose()?
+        .unwrap_or_default();
+    let expansion = cli::value(&args, "--expansion")
+        .map(|name| Expansion::from_name(name).ok_or(format!("unknown expansion {name}")))
+        .transpose()?
+        .unwrap_or_default();
+
+    let anm_files = cli::positional(&args, VALUE_OPTIONS)
         .filter(|arg| {
             Path::new(&arg)
                 .extension()
@@ -21,14 +37,24 @@ fn main() -> Result<(), Box<dyn Error>> {
         .collect::<Vec<_>>();
 
     if anm_files.is_empty() {
-        println!("Usage: anm2webp anm_file(s) ...");
+        println!(
+            "Usage: anm2webp [--transparency=none|index0|bit15|0xRGB555] [--expansion=replicate|hardware|linear] anm_file(s) ..."
+        );
         return Ok(());
     }
 
     for file in &anm_files {
-        let data = std::fs::read(file)?;
         let output = Path::new(file).with_extension("webp");
 
+        let mut data = Vec::new();
+        let reader = BufReader::new(File::open(file)?);
+        let Ok(mut reader) = DecompressReader::with_fallback(reader) else {
+            continue;
+        };
+        if reader.read_to_end(&mut data).is_err() {
+            continue;
+        }
+
         let mut reader = Cursor::new(&data);
         let _unknown: u32 = reader.read_le()?;
         let frame_count: u32 = reader.read_le()?;
@@ -58,9 +84,9 @@ fn main() -> Result<(), Box<dyn Error>> {
 
             let mut buf = [0; 2];
             let mut palette = Vec::with_capacity(palette_count);
-            for _ in 0..palette_count {
+            for i in 0..palette_count {
                 palette_data.read_exact(&mut buf)?;
-                let color = Color::from_rgb555(buf);
+                let color = transparency.color(i, buf, expansion);
                 palette.push(color);
             }
 
@@ -80,7 +106,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
         write_webp(&output, &frames, width as u32, height as u32);
 
-        if let Some(overlay_frames) = get_overlay_frames(file) {
+        if let Some(overlay_frames) = get_overlay_frames(file, expansion) {
             apply_overlay(
                 &mut frames,
                 &overlay_frames,
@@ -189,7 +215,7 @@ fn decompress_frame(
     Some(frame)
 }
 
-fn get_overlay_frames(anm_file: impl AsRef<Path>) -> Option<Vec<Vec<u8>>> {
+fn get_overlay_frames(anm_file: impl AsRef<Path>, expansion: Expansion) -> Option<Vec<Vec<u8>>> {
     let anm_file = anm_file.as_ref();
     let mut mtc_file = anm_file.file_stem()?.to_os_string();
     mtc_file.push(MTC_SUFFIX);
@@ -206,7 +232,7 @@ fn get_overlay_frames(anm_file: impl AsRef<Path>) -> Option<Vec<Vec<u8>>> {
 
         for _ in 0..MTC_WIDTH * MTC_HEIGHT {
             reader.read_exact(&mut buf).ok()?;
-            frame.extend(Color::from_rgb555(buf).as_ref());
+            frame.extend(Color::from_rgb555_with(buf, expansion).as_ref());
         }
 
         frames.push(frame);
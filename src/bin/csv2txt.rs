@@ -0,0 +1,171 @@
+use hoteldusk_tools::{
+    cli::{self, OutputPolicy},
+    text::{CodeTable, Encoding, Line},
+    txt::{csv, read_lines, write_lines},
+};
+use std::{error::Error, fs::File, path::Path};
+
+/// options that take a value
+const VALUE_OPTIONS: &[&str] = &["--codes", "--encoding", "--out-dir"];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let strict = cli::flag(&args, "--strict");
+    let output_policy = OutputPolicy::from_args(&args, "translated");
+    let encoding = cli::value(&args, "--encoding")
+        .map(|name| Encoding::from_name(name).ok_or(format!("unknown encoding {name}")))
+        .transpose()?;
+    let codes = match cli::value(&args, "--codes") {
+        Some(path) => CodeTable::from_file(path)?,
+        None => CodeTable::default(),
+    };
+
+    let tables = cli::positional(&args, VALUE_OPTIONS)
+        .filter_map(|arg| {
+            let ext = Path::new(arg).extension()?;
+            if ext.eq_ignore_ascii_case("csv") {
+                Some((arg, ','))
+            } else if ext.eq_ignore_ascii_case("tsv") {
+                Some((arg, '\t'))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if tables.is_empty() {
+        println!(
+            "Usage: csv2txt [--encoding=ascii|sjis|cp1252] [--codes=FILE] [--strict] [--out-dir=DIR | --in-place] csv_or_tsv_file(s) ..."
+        );
+        return Ok(());
+    }
+
+    'files: for (table, delimiter) in tables {
+        let table = Path::new(table);
+        let name = table.display();
+
+        let mut records = match csv::parse(&std::fs::read_to_string(table)?, delimiter) {
+            Ok(records) => records,
+            Err(err) => {
+                eprintln!("{name}: {err}");
+                continue;
+            }
+        };
+        if records
+            .first()
+            .is_some_and(|record| record.first().is_some_and(|field| field == "index"))
+        {
+            records.remove(0);
+        }
+
+        // foo.decoded.csv and foo.csv both come from foo.txt
+        let stem = table.file_stem().unwrap_or_default().to_string_lossy();
+        let stem = stem.strip_suffix(".decoded").unwrap_or(&stem);
+        let txt_path = table.with_file_name(format!("{stem}.txt"));
+        let original = match txt_path.exists() {
+            true => Some(read_lines(&mut File::open(&txt_path)?)?),
+            false => None,
+        };
+
+        if let Some(original) = &original
+            && original.len() != records.len()
+        {
+            eprintln!(
+                "{name}: has {} rows but {} has {} lines",
+                records.len(),
+                txt_path.display(),
+                original.len()
+            );
+            continue;
+        }
+
+        let mut rows = Vec::with_capacity(records.len());
+        for (i, record) in records.iter().enumerate() {
+            let row = i + 1;
+
+            // spreadsheets tend to drop trailing empty cells
+            let (index, source, translation) = match &record[..] {
+                [index, source] => (index, source, ""),
+                [index, source, translation, ..] => (index, source, translation.as_str()),
+                _ => {
+                    eprintln!("{name}: row {row} needs at least index and original columns");
+                    continue 'files;
+                }
+            };
+
+            if index.trim().parse() != Ok(i) {
+                eprintln!("{name}: row {row} has index {index:?}, expected {i}");
+                continue 'files;
+            }
+
+            rows.push((source.as_str(), translation));
+        }
+
+        let source_encoding = original.as_deref().map(Encoding::detect);
+        let encoding = encoding.or_else(|| {
+            let texts = rows.iter().flat_map(|(a, b)| [*a, *b]).collect::<Vec<_>>();
+            Encoding::for_translation(source_encoding, &texts)
+        });
+        let Some(encoding) = encoding else {
+            eprintln!("{name}: no single encoding fits the text, use --encoding");
+            continue;
+        };
+
+        let mut lines = Vec::with_capacity(rows.len());
+        for (i, (source, translation)) in rows.into_iter().enumerate() {
+            // untranslated lines are copied from the original file when there is one
+            if translation.is_empty()
+                && let Some(original) = &original
+            {
+                let line = match source_encoding {
+                    Some(source_encoding) if source_encoding != encoding => {
+                        let (line, _) = Line::decode(&original[i], source_encoding, &codes);
+                        line.encode(encoding).0
+                    }
+                    _ => original[i].clone(),
+                };
+                lines.push(line);
+                continue;
+            }
+
+            // a line break typed into a cell is kept as a raw 0x0A, the same byte as {n}
+            let markup = if translation.is_empty() {
+                source
+            } else {
+                translation
+            };
+            let line = match Line::from_markup(markup, &codes) {
+                Ok(line) => line,
+                Err(err) => {
+                    eprintln!("{name}: row {}: {err}", i + 1);
+                    continue 'files;
+                }
+            };
+
+            let (bytes, errors) = line.encode(encoding);
+            if !errors.is_empty() {
+                for c in &errors {
+                    eprintln!(
+                        "{name}: row {}: {c:?} has no {} mapping",
+                        i + 1,
+                        encoding.name()
+                    );
+                }
+                if strict {
+                    continue 'files;
+                }
+            }
+
+            lines.push(bytes);
+        }
+
+        let mut output = Vec::new();
+        write_lines(&mut output, &lines)?;
+        match output_policy.output_path(&txt_path) {
+            Ok(output_path) => std::fs::write(output_path, output)?,
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
+    Ok(())
+}
@@ -1,24 +1,37 @@
-use hoteldusk_tools::util::decompress;
-use std::{error::Error, fs::File, io::BufReader};
+use hoteldusk_tools::{
+    cli::{self, OutputPolicy},
+    util::{DecompressError, decompress},
+};
+use std::{error::Error, fs::File, io::BufReader, path::Path};
+
+/// options that take a value
+const VALUE_OPTIONS: &[&str] = &["--out-dir"];
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let files = std::env::args_os()
-        .skip(1)
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let output_policy = OutputPolicy::from_args(&args, "decompressed");
+
+    let files = cli::positional(&args, VALUE_OPTIONS)
         .filter(|arg| std::fs::metadata(arg).is_ok_and(|md| md.is_file()))
         .collect::<Vec<_>>();
 
     if files.is_empty() {
-        println!("Usage: decompressor file(s) ...");
+        println!("Usage: decompressor [--out-dir=DIR | --in-place] file(s) ...");
         return Ok(());
     }
 
-    for path in &files {
+    for path in files {
+        let path = Path::new(path);
         let file = File::open(path)?;
         let reader = &mut BufReader::new(file);
 
         match decompress(reader) {
-            Ok(data) => std::fs::write(path, data)?,
-            Err(_) => continue,
+            Ok(data) => match output_policy.output_path(path) {
+                Ok(output) => std::fs::write(output, data)?,
+                Err(err) => eprintln!("{err}"),
+            },
+            Err(DecompressError::UnknownMagic(_)) => continue,
+            Err(err) => eprintln!("{}: {err}", path.display()),
         }
     }
 
@@ -0,0 +1,52 @@
+use hoteldusk_tools::{cli, font::Font, image, text::Encoding};
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+const ATLAS_COLUMNS: usize = 16;
+
+/// options that take a value
+const VALUE_OPTIONS: &[&str] = &["--encoding"];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let encoding = cli::value(&args, "--encoding")
+        .map(|name| Encoding::from_name(name).ok_or(format!("unknown encoding {name}")))
+        .transpose()?
+        .unwrap_or(Encoding::ShiftJis);
+
+    let files = cli::positional(&args, VALUE_OPTIONS)
+        .filter(|arg| std::fs::metadata(arg).is_ok_and(|md| md.is_file()))
+        .collect::<Vec<_>>();
+
+    if files.is_empty() {
+        println!("Usage: font2png [--encoding=sjis|cp1252|ascii] font_file(s) ...");
+        return Ok(());
+    }
+
+    for file in files {
+        let path = Path::new(file);
+        let data = std::fs::read(path)?;
+
+        let font = match Font::read(&data) {
+            Ok(font) => font,
+            Err(err) => {
+                eprintln!("{}: {err}", path.display());
+                continue;
+            }
+        };
+
+        let (atlas, w, h) = font.atlas(ATLAS_COLUMNS);
+        let writer = BufWriter::new(File::create(path.with_extension("png"))?);
+        image::write_indexed_png(writer, w, h, &font.palette, &atlas, png::BitDepth::Four)?;
+
+        let mut metrics = BufWriter::new(File::create(path.with_extension("tsv"))?);
+        font.write_metrics(&mut metrics, encoding)?;
+        metrics.flush()?;
+    }
+
+    Ok(())
+}
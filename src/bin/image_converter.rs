@@ -1,147 +1,108 @@
-use hoteldusk_tools::util::{Color, ReadExt, WriteExt, decompress};
+use hoteldusk_tools::{
+    cli,
+    image::{self, Format, Image},
+    util::{Color, DecompressReader, Expansion, Transparency},
+};
 use std::{
     error::Error,
     fs::File,
-    io::{BufWriter, Cursor, Read, Seek},
+    io::{BufReader, BufWriter, Read},
     path::Path,
 };
 
+/// options that take a value
+const VALUE_OPTIONS: &[&str] = &["--expansion", "--transparency"];
+
 fn main() -> Result<(), Box<dyn Error>> {
-    let files = std::env::args_os()
-        .skip(1)
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let indexed = cli::flag(&args, "--indexed");
+    let transparency = cli::value(&args, "--transparency")
+        .map(|name| Transparency::from_name(name).ok_or(format!("unknown transparency {name}")))
+        .transpose()?
+        .unwrap_or_default();
+    let expansion = cli::value(&args, "--expansion")
+        .map(|name| Expansion::from_name(name).ok_or(format!("unknown expansion {name}")))
+        .transpose()?
+        .unwrap_or_default();
+
+    let files = cli::positional(&args, VALUE_OPTIONS)
         .filter(|arg| std::fs::metadata(arg).is_ok_and(|md| md.is_file()))
         .collect::<Vec<_>>();
 
     if files.is_empty() {
-        println!("Usage: image_converter file(s) ...");
+        println!(
+            "Usage: image_converter [--indexed] [--transparency=none|index0|bit15|0xRGB555] [--expansion=replicate|hardware|linear] file(s) ..."
+        );
         return Ok(());
     }
 
-    for file in &files {
-        let mut data = std::fs::read(file)?;
+    for file in files {
         let output = Path::new(file).with_extension("png");
 
-        if let Ok(decompressed) = decompress(&mut data.as_slice()) {
-            data = decompressed;
+        let mut data = Vec::new();
+        let reader = BufReader::new(File::open(file)?);
+        let Ok(mut reader) = DecompressReader::with_fallback(reader) else {
+            continue;
+        };
+        if reader.read_to_end(&mut data).is_err() {
+            continue;
         }
 
-        let mut reader = Cursor::new(&data);
-        let header = reader.read_bytes::<16>()?;
-
-        let (data, w, h) = if header == [0; 16] {
-            let _w: u16 = reader.read_le()?;
-            let _h: u16 = reader.read_le()?;
-            let width: u16 = reader.read_le()?;
-            let height: u16 = reader.read_le()?;
-            let _flag: u16 = reader.read_le()?;
-            let palette_count: u16 = reader.read_le()?;
-            let palette_offset: u16 = reader.read_le()?;
-            reader.seek_relative(2)?; // skip padding
-
-            if reader.position() != palette_offset as u64 {
+        let image = match image::decode_with(&data, transparency, expansion) {
+            Ok(image) => image,
+            Err(err) => {
+                eprintln!("{}: {err}", Path::new(file).display());
                 continue;
             }
-
-            let mut palette = Vec::with_capacity(palette_count as usize);
-            for _ in 0..palette_count {
-                let mut buf = [0; 2];
-                reader.read_exact(&mut buf)?;
-                let color = Color::from_rgb555(buf);
-                palette.push(color);
-            }
-
-            let pixel_data_len = width as usize * height as usize;
-            let mut pixel_data = Vec::with_capacity(pixel_data_len * size_of::<Color>());
-            for _ in 0..pixel_data_len {
-                let index: u8 = reader.read_le()?;
-                pixel_data.write_bytes(palette[index as usize % palette_count as usize])?;
-            }
-
-            (pixel_data, width as u32, height as u32)
-        } else {
-            const TILE_W: usize = 8;
-            const TILE_H: usize = 8;
-
-            reader.seek_relative(-16)?;
-            let _zero: u16 = reader.read_le()?;
-            let palette_count: u16 = reader.read_le()?;
-            let width: u16 = reader.read_le()?;
-            let height: u16 = reader.read_le()?;
-            let pixel_data_len: u32 = reader.read_le()?;
-            let palette_len: u16 = reader.read_le()?;
-            reader.seek_relative(2)?; // skip padding
-
-            if palette_count * 2 != palette_len {
-                continue;
-            }
-
-            if !width.is_multiple_of(TILE_W as u16) || !height.is_multiple_of(TILE_H as u16) {
-                eprintln!("image dimension isnt divisible by tile dimension");
-                continue;
-            }
-
-            let mut palette = Vec::with_capacity(palette_count as usize);
-            let mut buf = [0; 2];
-            for _ in 0..palette_count {
-                reader.read_exact(&mut buf)?;
-                let color = Color::from_rgb555(buf);
-                palette.push(color);
-            }
-
-            let indexes = match palette_count {
-                16 => {
-                    let mut indexes = Vec::with_capacity(pixel_data_len as usize * 2);
-                    for _ in 0..pixel_data_len {
-                        let byte: u8 = reader.read_le()?;
-                        indexes.push(byte & 0xF);
-                        indexes.push(byte >> 4);
-                    }
-                    indexes
-                }
-                256 => {
-                    let mut indexes = Vec::with_capacity(pixel_data_len as usize);
-                    for _ in 0..pixel_data_len {
-                        indexes.push(reader.read_le::<u8>()?);
-                    }
-                    indexes
-                }
-                other => {
-                    eprintln!("unknown palette count format {other}");
-                    continue;
-                }
-            };
-
-            let tiles = indexes.chunks_exact(TILE_W * TILE_H);
-            let tile_row_count = width as usize / TILE_W;
-
-            let mut pixel_data = vec![0; width as usize * height as usize * size_of::<Color>()];
-            for (i, tile) in tiles.enumerate() {
-                let tile_x = (i % tile_row_count) * TILE_W;
-                let tile_y = (i / tile_row_count) * TILE_H;
-
-                for (j, index) in tile.iter().copied().enumerate() {
-                    let x = (j % TILE_W) + tile_x;
-                    let y = (j / TILE_W) + tile_y;
-                    let pos = (y * width as usize + x) * 4;
-                    pixel_data[pos..][..4].clone_from_slice(palette[index as usize].as_ref());
-                }
-            }
-
-            (pixel_data, width as u32, height as u32)
         };
 
         let Ok(file) = File::create(output) else {
             continue;
         };
         let writer = BufWriter::new(file);
-        let mut encoder = png::Encoder::new(writer, w, h);
-        encoder.set_color(png::ColorType::Rgba);
-        encoder.set_depth(png::BitDepth::Eight);
-        let Ok(mut writer) = encoder.write_header() else {
-            continue;
-        };
-        writer.write_image_data(&data).ok();
+        if indexed {
+            let (palette, depth) = indexed_palette(&image);
+            image::write_indexed_png(
+                writer,
+                image.width,
+                image.height,
+                &palette,
+                &image.indices,
+                depth,
+            )
+            .ok();
+        } else {
+            let mut encoder = png::Encoder::new(writer, image.width, image.height);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let Ok(mut writer) = encoder.write_header() else {
+                continue;
+            };
+            writer.write_image_data(&image.to_rgba()).ok();
+        }
     }
 
     Ok(())
 }
+
+/// the palette of a palette png in the bit depth of the source
+fn indexed_palette(image: &Image) -> (Vec<Color>, png::BitDepth) {
+    // linear images wrap indexes past the end of the palette around
+    let color_count = image
+        .indices
+        .iter()
+        .map(|&index| index as usize + 1)
+        .max()
+        .unwrap_or_default()
+        .max(image.palette.len());
+    let palette = (0..color_count)
+        .map(|i| image.palette[i % image.palette.len()])
+        .collect();
+
+    let depth = match image.format {
+        Format::Tiled4 => png::BitDepth::Four,
+        Format::Linear { .. } | Format::Tiled8 => png::BitDepth::Eight,
+    };
+
+    (palette, depth)
+}
@@ -0,0 +1,110 @@
+use hoteldusk_tools::{
+    cli::{self, OutputPolicy},
+    font::Font,
+    image,
+    text::Encoding,
+    util::Color,
+};
+use std::{error::Error, io::Cursor, path::Path};
+
+const ATLAS_COLUMNS: usize = 16;
+
+/// options that take a value
+const VALUE_OPTIONS: &[&str] = &["--encoding", "--out-dir"];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let output_policy = OutputPolicy::from_args(&args, "new");
+    let encoding = cli::value(&args, "--encoding")
+        .map(|name| Encoding::from_name(name).ok_or(format!("unknown encoding {name}")))
+        .transpose()?
+        .unwrap_or(Encoding::ShiftJis);
+
+    let files = cli::positional(&args, VALUE_OPTIONS)
+        .filter(|arg| std::fs::metadata(arg).is_ok_and(|md| md.is_file()))
+        .collect::<Vec<_>>();
+
+    if files.is_empty() {
+        println!(
+            "Usage: png2font [--encoding=sjis|cp1252|ascii] [--out-dir=DIR | --in-place] original_font_file(s) ..."
+        );
+        return Ok(());
+    }
+
+    for file in files {
+        let path = Path::new(file);
+        let name = path.display();
+        let data = std::fs::read(path)?;
+
+        // foo.png and foo.tsv as written by font2png
+        let atlas_path = path.with_extension("png");
+        let metrics_path = path.with_extension("tsv");
+        if !atlas_path.exists() || !metrics_path.exists() {
+            eprintln!(
+                "{name}: needs {} and {}",
+                atlas_path.display(),
+                metrics_path.display()
+            );
+            continue;
+        }
+
+        let rebuilt = Font::read(&data).and_then(|mut font| {
+            let (atlas, width) = read_atlas(&atlas_path, &font.palette)?;
+            font.import_atlas(&atlas, width, ATLAS_COLUMNS)?;
+            font.read_metrics(&std::fs::read_to_string(&metrics_path)?, encoding)?;
+            font.write()
+        });
+
+        let rebuilt = match rebuilt {
+            Ok(rebuilt) => rebuilt,
+            Err(err) => {
+                eprintln!("{name}: {err}");
+                continue;
+            }
+        };
+
+        match output_policy.output_path(path) {
+            Ok(output_path) => std::fs::write(output_path, rebuilt)?,
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// the palette indexes of an indexed png, or of any other png matched to the
+/// nearest color of the font's palette
+fn read_atlas(path: &Path, palette: &[Color]) -> std::io::Result<(Vec<u8>, u32)> {
+    let data = std::fs::read(path)?;
+    if let Some(indexed) = image::read_indexed_png(&data)? {
+        return Ok((indexed.indices, indexed.width));
+    }
+
+    let mut decoder = png::Decoder::new(Cursor::new(&data));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size().unwrap_or_default()];
+    let info = reader.next_frame(&mut buf)?;
+
+    let rgb = |p: &[u8]| match info.color_type {
+        png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => [p[0]; 3],
+        _ => [p[0], p[1], p[2]],
+    };
+    let nearest = |[r, g, b]: [u8; 3]| {
+        let distance = |color: &Color| {
+            [(r, color.r()), (g, color.g()), (b, color.b())]
+                .iter()
+                .map(|&(x, y)| (x as i32 - y as i32).pow(2))
+                .sum::<i32>()
+        };
+        (0..palette.len())
+            .min_by_key(|&i| distance(&palette[i]))
+            .unwrap_or_default() as u8
+    };
+
+    let indices = buf[..info.buffer_size()]
+        .chunks_exact(info.color_type.samples())
+        .map(|p| nearest(rgb(p)))
+        .collect();
+    Ok((indices, info.width))
+}
@@ -0,0 +1,149 @@
+use hoteldusk_tools::{
+    cli::{self, OutputPolicy},
+    image::{self, Image},
+    util::{
+        CompressionMethod, DecompressReader, Expansion, detect_compression, write_lzss, write_rle,
+        write_stored,
+    },
+};
+use std::{
+    error::Error,
+    io::{Cursor, Read},
+    path::Path,
+};
+
+/// options that take a value
+const VALUE_OPTIONS: &[&str] = &["--compress", "--expansion", "--out-dir"];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let output_policy = OutputPolicy::from_args(&args, "new");
+    // the original's compression is kept unless asked otherwise
+    let compression = match cli::value(&args, "--compress") {
+        None => None,
+        Some("none") => Some(None),
+        Some("stored") => Some(Some(CompressionMethod::Stored)),
+        Some("lzss") => Some(Some(CompressionMethod::Lzss)),
+        Some("rle") => Some(Some(CompressionMethod::Rle)),
+        Some(method) => return Err(format!("unknown compression {method}").into()),
+    };
+    // has to match the expansion the png was exported with
+    let expansion = cli::value(&args, "--expansion")
+        .map(|name| Expansion::from_name(name).ok_or(format!("unknown expansion {name}")))
+        .transpose()?
+        .unwrap_or_default();
+
+    let files = cli::positional(&args, VALUE_OPTIONS)
+        .filter(|arg| std::fs::metadata(arg).is_ok_and(|md| md.is_file()))
+        .filter(|arg| {
+            !Path::new(arg)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+        })
+        .collect::<Vec<_>>();
+
+    if files.is_empty() {
+        println!(
+            "Usage: png2image [--compress=none|stored|lzss|rle] [--expansion=replicate|hardware|linear] [--out-dir=DIR | --in-place] original_image_file(s) ..."
+        );
+        return Ok(());
+    }
+
+    for file in files {
+        let path = Path::new(file);
+        let name = path.display();
+        let original = std::fs::read(path)?;
+
+        // foo.png as written by image_converter
+        let png_path = path.with_extension("png");
+        if !png_path.exists() {
+            eprintln!("{name}: needs {}", png_path.display());
+            continue;
+        }
+
+        let mut data = Vec::new();
+        let decompressed = DecompressReader::with_fallback(&original[..])
+            .map_err(std::io::Error::from)
+            .and_then(|mut reader| reader.read_to_end(&mut data));
+        let reference = match decompressed.and_then(|_| image::decode(&data)) {
+            Ok(reference) => reference,
+            Err(err) => {
+                eprintln!("{name}: {err}");
+                continue;
+            }
+        };
+
+        let encoded = read_png(&png_path, reference.format, expansion)
+            .and_then(|image| image::encode_with(&image, expansion));
+        let encoded = match encoded {
+            Ok(encoded) => encoded,
+            Err(err) => {
+                eprintln!("{}: {err}", png_path.display());
+                continue;
+            }
+        };
+
+        let method =
+            compression.unwrap_or_else(|| detect_compression(&original).map(|info| info.method));
+        let mut output = Vec::new();
+        let written = match method {
+            None => {
+                output = encoded;
+                Ok(())
+            }
+            Some(CompressionMethod::Stored) => write_stored(&mut output, &encoded),
+            Some(CompressionMethod::Lzss) => write_lzss(&mut output, &encoded),
+            Some(CompressionMethod::Rle) => write_rle(&mut output, &encoded),
+        };
+        if let Err(err) = written {
+            eprintln!("{name}: {err}");
+            continue;
+        }
+
+        match output_policy.output_path(path) {
+            Ok(output_path) => std::fs::write(output_path, output)?,
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// palette png files are taken as is, other ones are quantized
+fn read_png(path: &Path, format: image::Format, expansion: Expansion) -> std::io::Result<Image> {
+    let data = std::fs::read(path)?;
+
+    if let Some(indexed) = image::read_indexed_png(&data)? {
+        return Ok(Image {
+            width: indexed.width,
+            height: indexed.height,
+            palette: indexed.palette,
+            indices: indexed.indices,
+            format,
+        });
+    }
+
+    let mut decoder = png::Decoder::new(Cursor::new(&data));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+
+    let mut buf = vec![0; reader.output_buffer_size().unwrap_or_default()];
+    let frame = reader.next_frame(&mut buf)?;
+    let pixels = &buf[..frame.buffer_size()];
+
+    let rgba = match frame.color_type {
+        png::ColorType::Grayscale => pixels.iter().flat_map(|&y| [y, y, y, 0xFF]).collect(),
+        png::ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 0xFF])
+            .collect(),
+        png::ColorType::Rgba => pixels.to_vec(),
+        png::ColorType::Indexed => return Err(std::io::Error::other("unexpected indexed png")),
+    };
+
+    Image::from_rgba(frame.width, frame.height, &rgba, format, expansion)
+}
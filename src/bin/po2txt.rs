@@ -0,0 +1,172 @@
+use hoteldusk_tools::{
+    cli::{self, OutputPolicy},
+    text::{CodeTable, Encoding, Line},
+    txt::{
+        po::{parse_context, parse_po},
+        read_lines, write_lines,
+    },
+};
+use std::{collections::HashMap, error::Error, fs::File, path::Path};
+
+/// options that take a value
+const VALUE_OPTIONS: &[&str] = &["--codes", "--encoding", "--out-dir"];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let strict = cli::flag(&args, "--strict");
+    let output_policy = OutputPolicy::from_args(&args, "translated");
+    let encoding = cli::value(&args, "--encoding")
+        .map(|name| Encoding::from_name(name).ok_or(format!("unknown encoding {name}")))
+        .transpose()?;
+    let codes = match cli::value(&args, "--codes") {
+        Some(path) => CodeTable::from_file(path)?,
+        None => CodeTable::default(),
+    };
+
+    let has_extension = |arg: &&_, extension| {
+        Path::new(arg)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+    };
+    let po_files = cli::positional(&args, VALUE_OPTIONS)
+        .filter(|arg| has_extension(arg, "po"))
+        .collect::<Vec<_>>();
+    let txt_files = cli::positional(&args, VALUE_OPTIONS)
+        .filter(|arg| has_extension(arg, "txt"))
+        .collect::<Vec<_>>();
+
+    if po_files.is_empty() || txt_files.is_empty() {
+        println!(
+            "Usage: po2txt [--encoding=ascii|sjis|cp1252] [--codes=FILE] [--strict] [--out-dir=DIR | --in-place] po_file(s) txt_file(s) ..."
+        );
+        return Ok(());
+    }
+
+    // file name -> line index -> (msgid, msgstr)
+    let mut translations = HashMap::<String, HashMap<usize, (String, String)>>::new();
+    for po_file in po_files {
+        let text = std::fs::read_to_string(po_file)?;
+        for entry in parse_po(&text)? {
+            if entry.fuzzy || entry.translation.is_empty() {
+                continue;
+            }
+
+            let Some((file_name, index)) = parse_context(&entry.context) else {
+                continue;
+            };
+            translations
+                .entry(file_name.to_string())
+                .or_default()
+                .insert(index, (entry.id, entry.translation));
+        }
+    }
+
+    if translations.is_empty() {
+        eprintln!("no translations found");
+        return Ok(());
+    }
+
+    'files: for txt_file in txt_files {
+        let path = Path::new(txt_file);
+        let name = path.display();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        let Some(file_translations) = translations.get(file_name.as_ref()) else {
+            continue;
+        };
+
+        let mut lines = match read_lines(&mut File::open(path)?) {
+            Ok(lines) => lines,
+            Err(err) => {
+                eprintln!("{name}: {err}");
+                continue;
+            }
+        };
+        // decode with the encoding txt2po used, so the msgids compare equal
+        let source_encoding = encoding.unwrap_or_else(|| Encoding::detect(&lines));
+
+        let mut indexes = file_translations.keys().copied().collect::<Vec<_>>();
+        indexes.sort();
+
+        let mut translated = Vec::new();
+        for index in indexes {
+            let (id, translation) = &file_translations[&index];
+
+            let Some(line) = lines.get(index) else {
+                eprintln!("{name}:{}: no such line", index + 1);
+                continue;
+            };
+
+            // don't apply translations made for a different version of the line
+            let source = Line::decode(line, source_encoding, &codes).0;
+            if source.to_markup(&codes) != *id {
+                eprintln!("{name}:{}: source text changed, skipping", index + 1);
+                continue;
+            }
+
+            match Line::from_markup(translation, &codes) {
+                Ok(line) => translated.push((index, line)),
+                Err(err) => {
+                    eprintln!("{name}:{}: {err}", index + 1);
+                    continue 'files;
+                }
+            }
+        }
+
+        let mut texts = lines
+            .iter()
+            .map(|line| Line::decode(line, source_encoding, &codes).0)
+            .collect::<Vec<_>>();
+        for (index, line) in &translated {
+            texts[*index] = line.clone();
+        }
+        let texts = texts
+            .iter()
+            .map(|line| line.to_markup(&codes))
+            .collect::<Vec<_>>();
+
+        let output_encoding =
+            encoding.or_else(|| Encoding::for_translation(Some(source_encoding), &texts));
+        let Some(output_encoding) = output_encoding else {
+            eprintln!("{name}: no single encoding fits the translation, use --encoding");
+            continue;
+        };
+
+        // untranslated lines have to follow when the encoding changes
+        if output_encoding != source_encoding {
+            for line in lines.iter_mut() {
+                *line = Line::decode(line, source_encoding, &codes)
+                    .0
+                    .encode(output_encoding)
+                    .0;
+            }
+        }
+
+        for (index, line) in translated {
+            let (bytes, errors) = line.encode(output_encoding);
+            if !errors.is_empty() {
+                for c in &errors {
+                    eprintln!(
+                        "{name}:{}: {c:?} has no {} mapping",
+                        index + 1,
+                        output_encoding.name()
+                    );
+                }
+                if strict {
+                    continue 'files;
+                }
+            }
+
+            lines[index] = bytes;
+        }
+
+        let mut output = Vec::new();
+        write_lines(&mut output, &lines)?;
+        match output_policy.output_path(path) {
+            Ok(output_path) => std::fs::write(output_path, output)?,
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
+    Ok(())
+}
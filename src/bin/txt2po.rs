@@ -0,0 +1,89 @@
+use hoteldusk_tools::{
+    cli,
+    text::{CodeTable, Encoding, Line},
+    txt::{
+        po::{PoEntry, context, write_po},
+        read_lines,
+    },
+};
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// options that take a value
+const VALUE_OPTIONS: &[&str] = &["--codes", "--encoding", "--out"];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let combined_output = cli::value(&args, "--out");
+    let encoding = cli::value(&args, "--encoding")
+        .map(|name| Encoding::from_name(name).ok_or(format!("unknown encoding {name}")))
+        .transpose()?;
+    let codes = match cli::value(&args, "--codes") {
+        Some(path) => CodeTable::from_file(path)?,
+        None => CodeTable::default(),
+    };
+
+    let txt_files = cli::positional(&args, VALUE_OPTIONS)
+        .filter(|arg| {
+            Path::new(&arg)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"))
+        })
+        .collect::<Vec<_>>();
+
+    if txt_files.is_empty() {
+        println!(
+            "Usage: txt2po [--encoding=ascii|sjis|cp1252] [--codes=FILE] [--out=FILE.pot] txt_file(s) ..."
+        );
+        return Ok(());
+    }
+
+    let mut all_entries = Vec::new();
+
+    for txt_file in txt_files {
+        let path = Path::new(txt_file);
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        let lines = match read_lines(&mut File::open(path)?) {
+            Ok(lines) => lines,
+            Err(err) => {
+                eprintln!("{}: {err}", path.display());
+                continue;
+            }
+        };
+        let encoding = encoding.unwrap_or_else(|| Encoding::detect(&lines));
+
+        // gettext reserves the empty msgid for the header, empty lines have nothing to translate anyway
+        let entries = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(i, line)| PoEntry {
+                context: context(&file_name, i),
+                id: Line::decode(line, encoding, &codes).0.to_markup(&codes),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        match combined_output {
+            Some(_) => all_entries.extend(entries),
+            None => write_catalog(&path.with_extension("pot"), &entries)?,
+        }
+    }
+
+    if let Some(output) = combined_output {
+        write_catalog(Path::new(output), &all_entries)?;
+    }
+
+    Ok(())
+}
+
+fn write_catalog(path: &Path, entries: &[PoEntry]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_po(&mut writer, entries)?;
+    writer.flush()
+}
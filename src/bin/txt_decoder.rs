@@ -1,14 +1,37 @@
-use hoteldusk_tools::util::ReadExt;
+use hoteldusk_tools::{
+    cli::{self, OutputPolicy},
+    text::{CodeTable, Encoding, Line},
+    txt::{csv, read_lines},
+};
 use std::{
     error::Error,
     fs::File,
-    io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    io::{BufWriter, Write},
     path::Path,
 };
 
+/// options that take a value
+const VALUE_OPTIONS: &[&str] = &["--codes", "--encoding", "--format", "--out-dir"];
+
 fn main() -> Result<(), Box<dyn Error>> {
-    let txt_files = std::env::args_os()
-        .skip(1)
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let strict = cli::flag(&args, "--strict");
+    let output_policy = OutputPolicy::from_args(&args, "decoded");
+    let encoding = cli::value(&args, "--encoding")
+        .map(|name| Encoding::from_name(name).ok_or(format!("unknown encoding {name}")))
+        .transpose()?;
+    let codes = match cli::value(&args, "--codes") {
+        Some(path) => CodeTable::from_file(path)?,
+        None => CodeTable::default(),
+    };
+    let delimiter = match cli::value(&args, "--format") {
+        None | Some("text") => None,
+        Some("csv") => Some(','),
+        Some("tsv") => Some('\t'),
+        Some(format) => return Err(format!("unknown format {format}").into()),
+    };
+
+    let txt_files = cli::positional(&args, VALUE_OPTIONS)
         .filter(|arg| {
             Path::new(&arg)
                 .extension()
@@ -17,35 +40,74 @@ fn main() -> Result<(), Box<dyn Error>> {
         .collect::<Vec<_>>();
 
     if txt_files.is_empty() {
-        println!("Usage: txt_decoder txt_file(s) ...");
+        println!(
+            "Usage: txt_decoder [--encoding=ascii|sjis|cp1252] [--codes=FILE] [--format=text|csv|tsv] [--strict] [--out-dir=DIR | --in-place] txt_file(s) ..."
+        );
         return Ok(());
     }
 
-    for txt_file in &txt_files {
+    'files: for txt_file in txt_files {
         let mut file = File::open(txt_file)?;
-        let lines_count: u32 = file.read_le()?;
-        let lines_start = 4 + lines_count * 4;
-
-        let mut buf = vec![0; 4 * lines_count as usize];
-        file.read_exact(&mut buf)?;
-        let mut lines_table = buf.as_slice();
+        let lines = match read_lines(&mut file) {
+            Ok(lines) => lines,
+            Err(err) => {
+                eprintln!("{}: {err}", Path::new(txt_file).display());
+                continue;
+            }
+        };
+        let encoding = encoding.unwrap_or_else(|| Encoding::detect(&lines));
 
-        let mut buf_reader = BufReader::new(&file);
         let mut buf_writer = BufWriter::new(vec![]);
-        for _ in 0..lines_count {
-            let mut lines_buf = Vec::new();
-            let offset = lines_start + lines_table.read_le::<u32>()?;
+        if let Some(delimiter) = delimiter {
+            // excel only reads csv as utf-8 with a bom
+            buf_writer.write_all("\u{FEFF}".as_bytes())?;
+            csv::write_record(
+                &mut buf_writer,
+                &["index", "original", "translation"],
+                delimiter,
+            )?;
+        }
+
+        for (i, line) in lines.iter().enumerate() {
+            let (line_tokens, errors) = Line::decode(line, encoding, &codes);
 
-            buf_reader.seek(SeekFrom::Start(offset as u64))?;
-            buf_reader.read_until(0, &mut lines_buf)?;
+            if strict && !errors.is_empty() {
+                for pos in errors {
+                    eprintln!(
+                        "{}:{}: byte 0x{:02X} at {pos} is not valid {}",
+                        Path::new(txt_file).display(),
+                        i + 1,
+                        line[pos],
+                        encoding.name()
+                    );
+                }
+                continue 'files;
+            }
 
-            // remove null byte
-            lines_buf.pop();
-            buf_writer.write_all(&lines_buf)?;
-            buf_writer.write_all(b"\n")?;
+            let markup = line_tokens.to_markup(&codes);
+            match delimiter {
+                Some(delimiter) => {
+                    let index = i.to_string();
+                    csv::write_record(&mut buf_writer, &[index.as_str(), &markup, ""], delimiter)?
+                }
+                None => {
+                    buf_writer.write_all(markup.as_bytes())?;
+                    buf_writer.write_all(b"\n")?;
+                }
+            }
         }
 
-        std::fs::write(txt_file, buf_writer.into_inner()?).ok();
+        let output = output_policy
+            .output_path(Path::new(txt_file))
+            .map(|output| match delimiter {
+                Some(',') => output.with_extension("csv"),
+                Some(_) => output.with_extension("tsv"),
+                None => output,
+            });
+        match output {
+            Ok(output) => std::fs::write(output, buf_writer.into_inner()?)?,
+            Err(err) => eprintln!("{err}"),
+        }
     }
 
     Ok(())
@@ -0,0 +1,113 @@
+use hoteldusk_tools::{
+    cli::{self, OutputPolicy},
+    text::{CodeTable, Encoding, Line},
+    txt::write_lines,
+};
+use std::{
+    error::Error,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+/// options that take a value
+const VALUE_OPTIONS: &[&str] = &["--codes", "--encoding", "--out-dir"];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let strict = cli::flag(&args, "--strict");
+    let output_policy = OutputPolicy::from_args(&args, "encoded");
+    let encoding = cli::value(&args, "--encoding")
+        .map(|name| Encoding::from_name(name).ok_or(format!("unknown encoding {name}")))
+        .transpose()?;
+    let codes = match cli::value(&args, "--codes") {
+        Some(path) => CodeTable::from_file(path)?,
+        None => CodeTable::default(),
+    };
+
+    let txt_files = cli::positional(&args, VALUE_OPTIONS)
+        .filter(|arg| {
+            Path::new(&arg)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"))
+        })
+        .collect::<Vec<_>>();
+
+    if txt_files.is_empty() {
+        println!(
+            "Usage: txt_encoder [--encoding=ascii|sjis|cp1252] [--codes=FILE] [--strict] [--out-dir=DIR | --in-place] txt_file(s) ..."
+        );
+        return Ok(());
+    }
+
+    'files: for txt_file in txt_files {
+        let path = Path::new(txt_file).display();
+        let Ok(text) = String::from_utf8(std::fs::read(txt_file)?) else {
+            eprintln!("{path}: not utf-8 text");
+            continue;
+        };
+        let text = text.strip_prefix('\u{FEFF}').unwrap_or(&text);
+
+        // every decoded line ends with a newline, including the last one
+        let mut lines = text
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .collect::<Vec<_>>();
+        if lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+
+        let Some(encoding) = encoding.or_else(|| Encoding::detect_for_text(&lines)) else {
+            eprintln!("{path}: no single encoding fits the text, use --encoding");
+            continue;
+        };
+
+        let mut encoded = Vec::with_capacity(lines.len());
+        for (i, line) in lines.iter().enumerate() {
+            let line = match Line::from_markup(line, &codes) {
+                Ok(line) => line,
+                Err(err) => {
+                    eprintln!("{path}:{}: {err}", i + 1);
+                    continue 'files;
+                }
+            };
+            let (bytes, errors) = line.encode(encoding);
+
+            if strict && !errors.is_empty() {
+                for c in errors {
+                    eprintln!("{path}:{}: {c:?} has no {} mapping", i + 1, encoding.name());
+                }
+                continue 'files;
+            }
+
+            encoded.push(bytes);
+        }
+
+        let mut output = Vec::new();
+        write_lines(&mut output, &encoded)?;
+        match output_path(&output_policy, Path::new(txt_file)) {
+            Ok(path) => std::fs::write(path, output)?,
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
+    Ok(())
+}
+
+// foo.decoded.txt becomes foo.encoded.txt rather than foo.decoded.encoded.txt
+fn output_path(output_policy: &OutputPolicy, input: &Path) -> std::io::Result<PathBuf> {
+    let decoded_stem = input
+        .file_stem()
+        .and_then(|stem| stem.to_str()?.strip_suffix(".decoded"));
+
+    match (output_policy, decoded_stem) {
+        (OutputPolicy::Suffix(_), Some(stem)) => {
+            let mut name = OsString::from(stem);
+            if let Some(ext) = input.extension() {
+                name.push(".");
+                name.push(ext);
+            }
+            output_policy.output_path(&input.with_file_name(name))
+        }
+        _ => output_policy.output_path(input),
+    }
+}
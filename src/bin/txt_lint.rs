@@ -0,0 +1,123 @@
+use hoteldusk_tools::{
+    cli,
+    text::{
+        CodeTable, Encoding, Line,
+        layout::{Metrics, WidthTable, measure},
+    },
+    txt::read_lines,
+};
+use std::{error::Error, fs::File, path::Path};
+
+/// options that take a value
+const VALUE_OPTIONS: &[&str] = &[
+    "--box-lines",
+    "--box-width",
+    "--codes",
+    "--default-width",
+    "--encoding",
+    "--variable-width",
+    "--widths",
+];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let number = |name| -> Result<Option<u32>, String> {
+        cli::value(&args, name)
+            .map(|value| value.parse().map_err(|_| format!("{name} needs a number")))
+            .transpose()
+    };
+
+    let box_width = number("--box-width")?;
+    let box_lines = number("--box-lines")?;
+    let default_width = number("--default-width")?.unwrap_or(8);
+    let variable_width = number("--variable-width")?.unwrap_or(0);
+    let encoding = cli::value(&args, "--encoding")
+        .map(|name| Encoding::from_name(name).ok_or(format!("unknown encoding {name}")))
+        .transpose()?;
+    let codes = match cli::value(&args, "--codes") {
+        Some(path) => CodeTable::from_file(path)?,
+        None => CodeTable::default(),
+    };
+    let widths = match cli::value(&args, "--widths") {
+        Some(path) => Some(WidthTable::from_file(path)?),
+        None => None,
+    };
+
+    let txt_files = cli::positional(&args, VALUE_OPTIONS)
+        .filter(|arg| {
+            Path::new(&arg)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"))
+        })
+        .collect::<Vec<_>>();
+
+    let Some(box_width) = box_width.filter(|_| !txt_files.is_empty()) else {
+        println!(
+            "Usage: txt_lint --box-width=N [--box-lines=N] [--widths=FILE] [--default-width=N] [--variable-width=N] [--encoding=ascii|sjis|cp1252] [--codes=FILE] txt_file(s) ..."
+        );
+        return Ok(());
+    };
+
+    let empty_widths = WidthTable::default();
+    let metrics = Metrics {
+        widths: widths.as_ref().unwrap_or(&empty_widths),
+        codes: &codes,
+        default_width,
+        variable_width,
+    };
+
+    let mut issues = 0;
+
+    for txt_file in txt_files {
+        let path = Path::new(txt_file).display();
+        let lines = match read_lines(&mut File::open(txt_file)?) {
+            Ok(lines) => lines,
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                continue;
+            }
+        };
+        let encoding = encoding.unwrap_or_else(|| Encoding::detect(&lines));
+
+        for (i, line) in lines.iter().enumerate() {
+            let line_number = i + 1;
+            let measurement = measure(&Line::decode(line, encoding, &codes).0, &metrics);
+
+            for (row, width) in measurement.rows.iter().enumerate() {
+                if *width > box_width {
+                    println!(
+                        "{path}:{line_number}: row {} is {width}px wide, the box fits {box_width}px",
+                        row + 1
+                    );
+                    issues += 1;
+                }
+            }
+
+            if let Some(box_lines) = box_lines
+                && measurement.rows.len() > box_lines as usize
+            {
+                println!(
+                    "{path}:{line_number}: {} rows, the box fits {box_lines}",
+                    measurement.rows.len()
+                );
+                issues += 1;
+            }
+
+            // without a table every character is assumed to be default width
+            if widths.is_some() && !measurement.missing.is_empty() {
+                println!(
+                    "{path}:{line_number}: no glyph width for {:?}",
+                    measurement.missing
+                );
+                issues += 1;
+            }
+        }
+    }
+
+    if issues > 0 {
+        println!("{issues} issue(s) found");
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
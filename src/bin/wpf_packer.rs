@@ -0,0 +1,40 @@
+use hoteldusk_tools::wpf::{MANIFEST_NAME, Manifest, Writer};
+use std::{error::Error, fs::File, io::BufWriter, path::Path};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let dirs = std::env::args_os()
+        .skip(1)
+        .filter(|arg| std::fs::metadata(arg).is_ok_and(|md| md.is_dir()))
+        .collect::<Vec<_>>();
+
+    if dirs.is_empty() {
+        println!("Usage: wpf_packer unpacked_dir(s) ...");
+        return Ok(());
+    }
+
+    for dir in &dirs {
+        let dir = Path::new(dir);
+        let output = dir.with_extension("wpf");
+
+        if output.exists() {
+            eprintln!("{} already exists, not overwriting it", output.display());
+            continue;
+        }
+
+        let Ok(manifest_file) = File::open(dir.join(MANIFEST_NAME)) else {
+            eprintln!("{} has no {MANIFEST_NAME}", dir.display());
+            continue;
+        };
+        let manifest: Manifest = serde_json::from_reader(manifest_file)?;
+
+        let file = BufWriter::new(File::create(&output)?);
+        let mut writer = Writer::new(file, manifest.alignment);
+        for entry in &manifest.entries {
+            let data = std::fs::read(dir.join(entry.file_name()))?;
+            writer.add(entry, &data)?;
+        }
+        writer.finish(manifest.tail_len)?;
+    }
+
+    Ok(())
+}
@@ -1,10 +1,5 @@
-use hoteldusk_tools::util::ReadExt;
-use std::{
-    error::Error,
-    fs::File,
-    io::{BufReader, Read, Seek, SeekFrom},
-    path::Path,
-};
+use hoteldusk_tools::wpf::{Archive, MANIFEST_NAME};
+use std::{error::Error, fs::File, io::BufReader, path::Path};
 
 fn main() -> Result<(), Box<dyn Error>> {
     let wpf_files = std::env::args_os()
@@ -22,31 +17,19 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
 
     for wpf in &wpf_files {
-        let mut file = BufReader::new(File::open(wpf)?);
+        let mut archive = Archive::new(BufReader::new(File::open(wpf)?))?;
         let output_path = Path::new(wpf).with_extension("");
         std::fs::create_dir(&output_path).ok();
 
-        let mut name_buf = [0; 24];
-        while file.read_exact(&mut name_buf).is_ok() {
-            let end = name_buf
-                .iter()
-                .position(|&b| b == 0)
-                .unwrap_or(name_buf.len());
-
-            if end == 0 {
-                break;
-            }
-
-            let name = str::from_utf8(&name_buf[1..end])?;
-            let size: u32 = file.read_le()?;
-            let next: u32 = file.read_le()?;
-
-            let path = output_path.join(name);
-            let mut data = vec![0; size as usize];
-            file.read_exact(&mut data)?;
-            std::fs::write(path, data)?;
-            file.seek(SeekFrom::Start(next as u64))?;
+        // members are read by position, names aren't always unique
+        let manifest = archive.manifest();
+        for (i, entry) in manifest.entries.iter().enumerate() {
+            let data = archive.read_at(i)?;
+            std::fs::write(output_path.join(entry.file_name()), data)?;
         }
+
+        let manifest_file = File::create(output_path.join(MANIFEST_NAME))?;
+        serde_json::to_writer_pretty(manifest_file, &manifest)?;
     }
 
     Ok(())
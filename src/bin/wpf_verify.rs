@@ -0,0 +1,60 @@
+use hoteldusk_tools::wpf::Archive;
+use std::{error::Error, io::Cursor, path::Path};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let wpf_files = std::env::args_os()
+        .skip(1)
+        .filter(|arg| {
+            Path::new(&arg)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("wpf"))
+        })
+        .collect::<Vec<_>>();
+
+    if wpf_files.is_empty() {
+        println!("Usage: wpf_verify wpf_file(s) ...");
+        return Ok(());
+    }
+
+    let mut failed = false;
+
+    for wpf in &wpf_files {
+        let path = Path::new(wpf).display();
+        let original = std::fs::read(wpf)?;
+
+        let rebuilt = match Archive::new(Cursor::new(&original)).and_then(|mut a| a.rebuild()) {
+            Ok(rebuilt) => rebuilt,
+            Err(err) => {
+                println!("{path}: failed to repack, {err}");
+                failed = true;
+                continue;
+            }
+        };
+
+        let mismatch = original
+            .iter()
+            .zip(&rebuilt)
+            .position(|(a, b)| a != b)
+            .or_else(|| {
+                (original.len() != rebuilt.len()).then_some(original.len().min(rebuilt.len()))
+            });
+
+        match mismatch {
+            None => println!("{path}: ok"),
+            Some(offset) => {
+                println!(
+                    "{path}: mismatch at offset 0x{offset:X} (original {} bytes, repacked {} bytes)",
+                    original.len(),
+                    rebuilt.len()
+                );
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
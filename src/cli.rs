@@ -0,0 +1,105 @@
+use std::{
+    ffi::OsString,
+    io::{Error, Result},
+    path::{Path, PathBuf},
+};
+
+/// the arguments that aren't options or the values of the given options,
+/// which take their value either as `--name=value` or `--name value`
+pub fn positional<'a>(
+    args: &'a [OsString],
+    value_options: &'a [&str],
+) -> impl Iterator<Item = &'a OsString> {
+    args.iter().enumerate().filter_map(|(i, arg)| {
+        let is_option = arg.to_str().is_some_and(|arg| arg.starts_with("--"));
+        let is_value = i > 0
+            && args[i - 1]
+                .to_str()
+                .is_some_and(|prev| value_options.contains(&prev));
+
+        (!is_option && !is_value).then_some(arg)
+    })
+}
+
+pub fn flag(args: &[OsString], name: &str) -> bool {
+    args.iter().any(|arg| arg == name)
+}
+
+/// the value of a `--name=value` or `--name value` argument
+pub fn value<'a>(args: &'a [OsString], name: &str) -> Option<&'a str> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        let arg = arg.to_str()?;
+
+        match arg.strip_prefix(name)? {
+            "" => args.get(i + 1)?.to_str(),
+            rest => rest.strip_prefix('='),
+        }
+    })
+}
+
+/// where tools that convert a file into the same kind of file write their output
+#[derive(Debug, Clone)]
+pub enum OutputPolicy {
+    /// next to the input, with `.suffix` inserted before the extension
+    Suffix(String),
+    /// into another directory with the same file name
+    OutDir(PathBuf),
+    /// overwrite the input
+    InPlace,
+}
+
+impl OutputPolicy {
+    /// `--in-place` or `--out-dir=DIR`, falling back to the given suffix
+    pub fn from_args(args: &[OsString], default_suffix: &str) -> Self {
+        if flag(args, "--in-place") {
+            Self::InPlace
+        } else if let Some(dir) = value(args, "--out-dir") {
+            Self::OutDir(PathBuf::from(dir))
+        } else {
+            Self::Suffix(default_suffix.to_string())
+        }
+    }
+
+    pub fn output_path(&self, input: &Path) -> Result<PathBuf> {
+        let output = match self {
+            Self::InPlace => return Ok(input.to_path_buf()),
+            Self::OutDir(dir) => {
+                let Some(name) = input.file_name() else {
+                    return Err(Error::other("input has no file name"));
+                };
+                std::fs::create_dir_all(dir)?;
+                dir.join(name)
+            }
+            Self::Suffix(suffix) => {
+                let Some(stem) = input.file_stem() else {
+                    return Err(Error::other("input has no file name"));
+                };
+                let mut name = stem.to_os_string();
+                name.push(".");
+                name.push(suffix);
+                if let Some(ext) = input.extension() {
+                    name.push(".");
+                    name.push(ext);
+                }
+                input.with_file_name(name)
+            }
+        };
+
+        // only --in-place is allowed to replace the input
+        if is_same_file(input, &output) {
+            return Err(Error::other(format!(
+                "{} would overwrite the input, use --in-place to allow it",
+                output.display()
+            )));
+        }
+
+        Ok(output)
+    }
+}
+
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
@@ -0,0 +1,395 @@
+use crate::{
+    image::{TILE_H, TILE_W, tile_indices, untile_indices},
+    text::Encoding,
+    util::{Color, ReadExt, WriteExt},
+};
+use std::io::{Error, ErrorKind, Result, Write};
+
+/// a palettized font with 4bpp glyphs, laid out as
+///
+/// ```text
+/// u16       glyph count
+/// u16       character count
+/// u8, u8    cell width and height, multiples of 8
+/// u16       padding
+/// 16 x u16  rgb555 palette
+/// glyphs    one cell each, 8x8 tiles in reading order as in tiled images
+/// chars     u16 code, u16 glyph index, u8 advance, u8 padding
+/// ```
+#[derive(Clone)]
+pub struct Font {
+    pub cell_width: u8,
+    pub cell_height: u8,
+    pub palette: Vec<Color>,
+    /// palette indexes of every glyph cell, in reading order
+    pub glyphs: Vec<Vec<u8>>,
+    pub char_map: Vec<CharEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CharEntry {
+    /// character code in the game's text encoding
+    pub code: u16,
+    pub glyph: u16,
+    pub advance: u8,
+}
+
+pub const PALETTE_COUNT: usize = 16;
+
+const HEADER_SIZE: usize = 8;
+const CHAR_ENTRY_SIZE: usize = 6;
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+impl Font {
+    pub fn read(data: &[u8]) -> Result<Self> {
+        let mut reader = data;
+        let glyph_count: u16 = reader.read_le()?;
+        let char_count: u16 = reader.read_le()?;
+        let cell_width: u8 = reader.read_le()?;
+        let cell_height: u8 = reader.read_le()?;
+        let _padding: u16 = reader.read_le()?;
+
+        if cell_width == 0
+            || cell_height == 0
+            || !(cell_width as usize).is_multiple_of(TILE_W)
+            || !(cell_height as usize).is_multiple_of(TILE_H)
+        {
+            return Err(invalid("cell size isn't a multiple of the tile size"));
+        }
+
+        let glyph_size = cell_width as usize * cell_height as usize / 2;
+        let glyphs_len = glyph_count as usize * glyph_size;
+        if data.len()
+            < HEADER_SIZE + PALETTE_COUNT * 2 + glyphs_len + char_count as usize * CHAR_ENTRY_SIZE
+        {
+            return Err(invalid("truncated font"));
+        }
+
+        let palette = (0..PALETTE_COUNT)
+            .map(|_| reader.read_bytes().map(Color::from_rgb555))
+            .collect::<Result<Vec<_>>>()?;
+
+        let (bitmaps, mut reader) = reader.split_at(glyphs_len);
+        let glyphs = bitmaps
+            .chunks_exact(glyph_size)
+            .map(|bitmap| {
+                let tiled = bitmap
+                    .iter()
+                    .flat_map(|byte| [byte & 0xF, byte >> 4])
+                    .collect::<Vec<_>>();
+                untile_indices(&tiled, cell_width as usize, cell_height as usize)
+            })
+            .collect();
+
+        let mut char_map = Vec::with_capacity(char_count as usize);
+        for _ in 0..char_count {
+            let code = reader.read_le()?;
+            let glyph = reader.read_le()?;
+            let advance = reader.read_le()?;
+            let _padding: u8 = reader.read_le()?;
+
+            if glyph >= glyph_count {
+                return Err(invalid("character mapped to a missing glyph"));
+            }
+
+            char_map.push(CharEntry {
+                code,
+                glyph,
+                advance,
+            });
+        }
+
+        Ok(Self {
+            cell_width,
+            cell_height,
+            palette,
+            glyphs,
+            char_map,
+        })
+    }
+
+    /// every glyph in a grid of `columns` cells per row as palette indexes,
+    /// returned with the width and height of the grid
+    pub fn atlas(&self, columns: usize) -> (Vec<u8>, u32, u32) {
+        let cell_w = self.cell_width as usize;
+        let cell_h = self.cell_height as usize;
+        let rows = self.glyphs.len().div_ceil(columns);
+        let width = columns * cell_w;
+
+        let mut atlas = vec![0; width * rows * cell_h];
+        for (i, glyph) in self.glyphs.iter().enumerate() {
+            let cell_x = (i % columns) * cell_w;
+            let cell_y = (i / columns) * cell_h;
+
+            for (j, &index) in glyph.iter().enumerate() {
+                atlas[(cell_y + j / cell_w) * width + cell_x + j % cell_w] = index;
+            }
+        }
+
+        (atlas, width as u32, (rows * cell_h) as u32)
+    }
+
+    /// replaces the glyphs with the cells of an atlas laid out like
+    /// [`Font::atlas`], adding glyphs for cells drawn past the end of the
+    /// glyph table
+    pub fn import_atlas(&mut self, atlas: &[u8], width: u32, columns: usize) -> Result<()> {
+        let cell_w = self.cell_width as usize;
+        let cell_h = self.cell_height as usize;
+        let width = width as usize;
+
+        if width != columns * cell_w
+            || width * cell_h == 0
+            || !atlas.len().is_multiple_of(width * cell_h)
+        {
+            return Err(invalid("atlas size doesn't match the font's cell size"));
+        }
+
+        if atlas.iter().any(|&index| index as usize >= PALETTE_COUNT) {
+            return Err(invalid("atlas uses colors past the font's palette"));
+        }
+
+        let cells = (atlas.len() / (width * cell_h)) * columns;
+        let cell = |i: usize| {
+            let cell_x = (i % columns) * cell_w;
+            let cell_y = (i / columns) * cell_h;
+            (0..cell_w * cell_h)
+                .map(|j| atlas[(cell_y + j / cell_w) * width + cell_x + j % cell_w])
+                .collect::<Vec<_>>()
+        };
+
+        for i in 0..cells {
+            let glyph = cell(i);
+            if i < self.glyphs.len() {
+                self.glyphs[i] = glyph;
+            } else if glyph.iter().any(|&index| index != 0) {
+                self.resize_glyphs(i);
+                self.glyphs.push(glyph);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// writes the character map as a tab separated table; codes that have no
+    /// character in `encoding` get an empty character column
+    pub fn write_metrics<W: Write>(&self, writer: &mut W, encoding: Encoding) -> Result<()> {
+        // the first two columns can be used as a width table for txt_lint
+        writeln!(writer, "# char\tadvance\tglyph\tcode")?;
+        for entry in &self.char_map {
+            let c = code_to_char(entry.code, encoding)
+                .map(|c| format!("U+{:04X}", c as u32))
+                .unwrap_or_default();
+
+            writeln!(
+                writer,
+                "{c}\t{}\t{}\t0x{:04X}",
+                entry.advance, entry.glyph, entry.code
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// replaces the character map with a table in the format of
+    /// [`Font::write_metrics`]; the code column may be left out for
+    /// characters `encoding` can represent, the character column for rows
+    /// that have a code
+    pub fn read_metrics(&mut self, text: &str, encoding: Encoding) -> Result<()> {
+        let mut char_map = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let bad_line = |what: &str| invalid(&format!("line {}: {what}", i + 1));
+            let columns = line.split('\t').collect::<Vec<_>>();
+            let &[c, advance, glyph, ..] = &columns[..] else {
+                return Err(bad_line("expected char, advance and glyph"));
+            };
+
+            let c = match c {
+                "" => None,
+                c => Some(parse_char(c).ok_or_else(|| bad_line("bad character"))?),
+            };
+            let advance = advance.parse().map_err(|_| bad_line("bad advance"))?;
+            let glyph: u16 = glyph.parse().map_err(|_| bad_line("bad glyph index"))?;
+            let code = match columns.get(3).map(|code| code.trim()) {
+                Some(code) if !code.is_empty() => code
+                    .strip_prefix("0x")
+                    .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| bad_line("bad code"))?,
+                _ => c
+                    .and_then(|c| char_to_code(c, encoding))
+                    .ok_or_else(|| bad_line("needs a code or a character the encoding has"))?,
+            };
+
+            // glyphs missing from the atlas are left blank
+            self.resize_glyphs(glyph as usize + 1);
+
+            char_map.push(CharEntry {
+                code,
+                glyph,
+                advance,
+            });
+        }
+
+        char_map.sort_by_key(|entry| entry.code);
+        if let Some(pair) = char_map
+            .windows(2)
+            .find(|pair| pair[0].code == pair[1].code)
+        {
+            return Err(invalid(&format!(
+                "character code 0x{:04X} is mapped twice",
+                pair[0].code
+            )));
+        }
+
+        self.char_map = char_map;
+        Ok(())
+    }
+
+    pub fn write(&self) -> Result<Vec<u8>> {
+        let glyph_count =
+            u16::try_from(self.glyphs.len()).map_err(|_| invalid("too many glyphs"))?;
+        let char_count =
+            u16::try_from(self.char_map.len()).map_err(|_| invalid("too many characters"))?;
+
+        if self.palette.len() != PALETTE_COUNT {
+            return Err(invalid("a font palette has 16 colors"));
+        }
+
+        let mut output = Vec::new();
+        output.write_le(glyph_count)?;
+        output.write_le(char_count)?;
+        output.write_le(self.cell_width)?;
+        output.write_le(self.cell_height)?;
+        output.write_le(0u16)?;
+
+        for color in &self.palette {
+            output.write_bytes(color.to_rgb555())?;
+        }
+
+        let cell_w = self.cell_width as usize;
+        let cell_h = self.cell_height as usize;
+        for glyph in &self.glyphs {
+            let tiled = tile_indices(glyph, cell_w, cell_h);
+            let bitmap = tiled
+                .chunks_exact(2)
+                .map(|pair| pair[0] | pair[1] << 4)
+                .collect::<Vec<_>>();
+            output.write_bytes(bitmap)?;
+        }
+
+        for entry in &self.char_map {
+            output.write_le(entry.code)?;
+            output.write_le(entry.glyph)?;
+            output.write_le(entry.advance)?;
+            output.write_le(0u8)?;
+        }
+
+        Ok(output)
+    }
+
+    fn resize_glyphs(&mut self, len: usize) {
+        if self.glyphs.len() < len {
+            let pixel_count = self.cell_width as usize * self.cell_height as usize;
+            self.glyphs.resize(len, vec![0; pixel_count]);
+        }
+    }
+}
+
+/// the character a code from the character map stands for
+pub fn code_to_char(code: u16, encoding: Encoding) -> Option<char> {
+    let bytes = match code {
+        0..=0xFF => vec![code as u8],
+        _ => code.to_be_bytes().to_vec(),
+    };
+
+    let (decoded, errors) = encoding.decode(&bytes);
+    let mut chars = decoded.chars();
+    match (errors.is_empty(), chars.next(), chars.next()) {
+        (true, Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// the character map code of a character, the inverse of [`code_to_char`]
+pub fn char_to_code(c: char, encoding: Encoding) -> Option<u16> {
+    let (bytes, errors) = encoding.encode(c.encode_utf8(&mut [0; 4]));
+    match (errors.is_empty(), &bytes[..]) {
+        (true, &[byte]) => Some(byte as u16),
+        (true, &[high, low]) => Some(u16::from_be_bytes([high, low])),
+        _ => None,
+    }
+}
+
+// `U+XXXX` or the character itself
+fn parse_char(s: &str) -> Option<char> {
+    if let Some(hex) = s.strip_prefix("U+") {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+    }
+
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CharEntry, Font};
+    use crate::{text::Encoding, util::Color};
+
+    const COLUMNS: usize = 16;
+
+    fn font() -> Font {
+        let glyphs = (0..3u8)
+            .map(|i| {
+                (0..16 * 8)
+                    .map(|j| (j as u8).wrapping_mul(7 + i) & 0xF)
+                    .collect()
+            })
+            .collect();
+        let char_map = [(0x0041, 0, 6), (0x82A0, 1, 12), (0xFFFF, 2, 8)]
+            .map(|(code, glyph, advance)| CharEntry {
+                code,
+                glyph,
+                advance,
+            })
+            .to_vec();
+
+        Font {
+            cell_width: 16,
+            cell_height: 8,
+            palette: (0..16).map(|i| Color::from_rgb555([i * 2, i])).collect(),
+            glyphs,
+            char_map,
+        }
+    }
+
+    #[test]
+    fn unedited_round_trip() {
+        let original = font().write().unwrap();
+        let font = Font::read(&original).unwrap();
+
+        let (atlas, width, _) = font.atlas(COLUMNS);
+        let mut metrics = Vec::new();
+        font.write_metrics(&mut metrics, Encoding::ShiftJis)
+            .unwrap();
+
+        // 0xFFFF has no character, but keeps its row
+        let metrics = String::from_utf8(metrics).unwrap();
+        assert!(metrics.contains("\n\t8\t2\t0xFFFF\n"));
+
+        let mut rebuilt = Font::read(&original).unwrap();
+        rebuilt.import_atlas(&atlas, width, COLUMNS).unwrap();
+        rebuilt.read_metrics(&metrics, Encoding::ShiftJis).unwrap();
+        assert_eq!(rebuilt.write().unwrap(), original);
+    }
+}
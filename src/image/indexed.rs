@@ -0,0 +1,174 @@
+use crate::util::Color;
+use std::io::{Cursor, Result, Write};
+
+/// the size, palette and indexes in reading order of a palette png
+pub struct IndexedPng {
+    pub width: u32,
+    pub height: u32,
+    pub palette: Vec<Color>,
+    pub indices: Vec<u8>,
+}
+
+/// reads a palette png as it is stored, `None` when the png has no palette.
+/// the palette comes back opaque
+pub fn read_indexed_png(data: &[u8]) -> Result<Option<IndexedPng>> {
+    let mut decoder = png::Decoder::new(Cursor::new(data));
+    decoder.set_transformations(png::Transformations::IDENTITY);
+    let mut reader = decoder.read_info()?;
+    let info = reader.info();
+
+    if info.color_type != png::ColorType::Indexed {
+        return Ok(None);
+    }
+
+    let (width, height) = (info.width, info.height);
+    let palette = info
+        .palette
+        .as_deref()
+        .unwrap_or_default()
+        .chunks_exact(3)
+        .map(|rgb| Color::from([rgb[0], rgb[1], rgb[2], 0xFF]))
+        .collect();
+
+    let mut buf = vec![0; reader.output_buffer_size().unwrap_or_default()];
+    let frame = reader.next_frame(&mut buf)?;
+
+    // rows of packed indexes, the most significant bits first
+    let bits = frame.bit_depth as usize;
+    let per_byte = 8 / bits;
+    let mask = ((1u16 << bits) - 1) as u8;
+    let indices = buf[..frame.buffer_size()]
+        .chunks_exact(frame.line_size)
+        .flat_map(|row| {
+            (0..width as usize).map(move |x| {
+                let shift = 8 - bits * (x % per_byte + 1);
+                (row[x / per_byte] >> shift) & mask
+            })
+        })
+        .collect();
+
+    Ok(Some(IndexedPng {
+        width,
+        height,
+        palette,
+        indices,
+    }))
+}
+
+/// writes palette indexes in reading order as a palette png of `depth` bits
+/// per pixel, the alpha of the palette going into a trns chunk unless every
+/// color is opaque
+pub fn write_indexed_png<W: Write>(
+    writer: W,
+    width: u32,
+    height: u32,
+    palette: &[Color],
+    indices: &[u8],
+    depth: png::BitDepth,
+) -> Result<()> {
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(depth);
+    encoder.set_palette(
+        palette
+            .iter()
+            .flat_map(|color| [color.r(), color.g(), color.b()])
+            .collect::<Vec<_>>(),
+    );
+
+    let mut trns = palette.iter().map(|color| color.a()).collect::<Vec<_>>();
+    while trns.last() == Some(&0xFF) {
+        trns.pop();
+    }
+    if !trns.is_empty() {
+        encoder.set_trns(trns);
+    }
+
+    // rows of packed indexes, the leftmost pixel in the most significant bits
+    let bits = depth as usize;
+    let data = indices
+        .chunks_exact(width.max(1) as usize)
+        .flat_map(|row| {
+            row.chunks(8 / bits).map(|pixels| {
+                pixels
+                    .iter()
+                    .enumerate()
+                    .fold(0, |byte, (i, &index)| byte | index << (8 - bits * (i + 1)))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    encoder.write_header()?.write_image_data(&data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_rows_most_significant_bits_first() {
+        let palette = [Color::from([0; 4]), Color::from([0xFF; 4])];
+        let indices = [1, 0, 1, 1, 0, 0, 0, 1, 1, 0];
+        let mut png = Vec::new();
+        write_indexed_png(&mut png, 5, 2, &palette, &indices, png::BitDepth::Four).unwrap();
+
+        let mut decoder = png::Decoder::new(Cursor::new(&png));
+        decoder.set_transformations(png::Transformations::IDENTITY);
+        let mut reader = decoder.read_info().unwrap();
+        assert_eq!(reader.info().trns.as_deref(), Some(&[0][..]));
+        let mut buf = vec![0; reader.output_buffer_size().unwrap()];
+        let frame = reader.next_frame(&mut buf).unwrap();
+        assert_eq!(
+            &buf[..frame.buffer_size()],
+            [0x10, 0x11, 0x00, 0x00, 0x11, 0x00]
+        );
+
+        let read = read_indexed_png(&png).unwrap().unwrap();
+        assert_eq!((read.width, read.height), (5, 2));
+        assert_eq!(read.indices, indices);
+    }
+
+    #[test]
+    fn round_trip_every_depth() {
+        let palette = (0..=255)
+            .map(|i| Color::from([i, i, i, 0xFF]))
+            .collect::<Vec<_>>();
+        for (depth, bits) in [
+            (png::BitDepth::One, 1),
+            (png::BitDepth::Two, 2),
+            (png::BitDepth::Four, 4),
+            (png::BitDepth::Eight, 8),
+        ] {
+            let palette = &palette[..1 << bits];
+            let indices = (0..7 * 3)
+                .map(|i| (i * 5 % (1 << bits)) as u8)
+                .collect::<Vec<_>>();
+            let mut png = Vec::new();
+            write_indexed_png(&mut png, 7, 3, palette, &indices, depth).unwrap();
+
+            let read = read_indexed_png(&png).unwrap().unwrap();
+            let rgba = |palette: &[Color]| {
+                palette
+                    .iter()
+                    .map(|color| [color.r(), color.g(), color.b(), color.a()])
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(rgba(&read.palette), rgba(palette));
+            assert_eq!(read.indices, indices);
+        }
+    }
+
+    #[test]
+    fn other_pngs_are_not_read() {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 1, 1);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&[1, 2, 3])
+            .unwrap();
+        assert!(read_indexed_png(&png).unwrap().is_none());
+    }
+}
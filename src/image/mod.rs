@@ -0,0 +1,397 @@
+mod indexed;
+mod quantize;
+
+pub use indexed::{IndexedPng, read_indexed_png, write_indexed_png};
+pub use quantize::quantize;
+
+use crate::util::{Color, Expansion, ReadExt, Transparency, WriteExt};
+use std::io::{Error, ErrorKind, Result};
+
+pub const TILE_W: usize = 8;
+pub const TILE_H: usize = 8;
+
+/// how the pixels of an image are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// one byte per pixel in reading order, after a 16 byte zero header
+    Linear {
+        /// not understood yet, kept so the image can be written back
+        unknown_w: u16,
+        unknown_h: u16,
+        flag: u16,
+    },
+    /// 8x8 tiles of 16 color indexes, two pixels per byte
+    Tiled4,
+    /// 8x8 tiles of 256 color indexes
+    Tiled8,
+}
+
+/// a paletted image, `indices` holding one palette index per pixel in reading order
+#[derive(Clone)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub palette: Vec<Color>,
+    pub indices: Vec<u8>,
+    pub format: Format,
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// decodes an already decompressed image file with an opaque palette
+pub fn decode(data: &[u8]) -> Result<Image> {
+    decode_with(data, Transparency::Opaque, Expansion::default())
+}
+
+/// decodes an already decompressed image file, making the palette colors
+/// picked by `transparency` transparent
+pub fn decode_with(data: &[u8], transparency: Transparency, expansion: Expansion) -> Result<Image> {
+    let mut reader = data;
+    let header = reader.read_bytes::<16>()?;
+    if header == [0; 16] {
+        decode_linear(reader, transparency, expansion)
+    } else {
+        decode_tiled(data, transparency, expansion)
+    }
+}
+
+fn decode_linear(
+    mut reader: &[u8],
+    transparency: Transparency,
+    expansion: Expansion,
+) -> Result<Image> {
+    let unknown_w: u16 = reader.read_le()?;
+    let unknown_h: u16 = reader.read_le()?;
+    let width: u16 = reader.read_le()?;
+    let height: u16 = reader.read_le()?;
+    let flag: u16 = reader.read_le()?;
+    let palette_count: u16 = reader.read_le()?;
+    let palette_offset: u16 = reader.read_le()?;
+    let _padding: u16 = reader.read_le()?;
+
+    // the palette follows the 32 byte header
+    if palette_offset != 32 {
+        return Err(invalid("unexpected palette offset"));
+    }
+    if palette_count == 0 {
+        return Err(invalid("image has no palette"));
+    }
+    if width == 0 || height == 0 {
+        return Err(invalid("image has no pixels"));
+    }
+
+    let palette = read_palette(&mut reader, palette_count, transparency, expansion)?;
+
+    let pixel_count = width as usize * height as usize;
+    let indices = reader
+        .get(..pixel_count)
+        .ok_or_else(|| invalid("truncated pixel data"))?
+        .to_vec();
+
+    Ok(Image {
+        width: width as u32,
+        height: height as u32,
+        palette,
+        indices,
+        format: Format::Linear {
+            unknown_w,
+            unknown_h,
+            flag,
+        },
+    })
+}
+
+fn decode_tiled(
+    mut reader: &[u8],
+    transparency: Transparency,
+    expansion: Expansion,
+) -> Result<Image> {
+    let _zero: u16 = reader.read_le()?;
+    let palette_count: u16 = reader.read_le()?;
+    let width: u16 = reader.read_le()?;
+    let height: u16 = reader.read_le()?;
+    let pixel_data_len: u32 = reader.read_le()?;
+    let palette_len: u16 = reader.read_le()?;
+    let _padding: u16 = reader.read_le()?;
+
+    if palette_count as u32 * 2 != palette_len as u32 {
+        return Err(invalid("palette size doesn't match the palette count"));
+    }
+
+    if width == 0 || height == 0 {
+        return Err(invalid("image has no pixels"));
+    }
+
+    if !width.is_multiple_of(TILE_W as u16) || !height.is_multiple_of(TILE_H as u16) {
+        return Err(invalid("image dimension isnt divisible by tile dimension"));
+    }
+
+    let palette = read_palette(&mut reader, palette_count, transparency, expansion)?;
+
+    let pixel_data = reader
+        .get(..pixel_data_len as usize)
+        .ok_or_else(|| invalid("truncated pixel data"))?;
+    let (tiled, format) = match palette_count {
+        16 => (
+            pixel_data
+                .iter()
+                .flat_map(|byte| [byte & 0xF, byte >> 4])
+                .collect(),
+            Format::Tiled4,
+        ),
+        256 => (pixel_data.to_vec(), Format::Tiled8),
+        _ => return Err(invalid("unknown palette count format")),
+    };
+
+    let indices = untile_indices(&tiled, width as usize, height as usize);
+
+    Ok(Image {
+        width: width as u32,
+        height: height as u32,
+        palette,
+        indices,
+        format,
+    })
+}
+
+/// encodes an image in its format, without compression
+pub fn encode(image: &Image) -> Result<Vec<u8>> {
+    encode_with(image, Expansion::default())
+}
+
+/// encodes an image in its format, without compression, reducing the
+/// palette with the inverse of `expansion`
+pub fn encode_with(image: &Image, expansion: Expansion) -> Result<Vec<u8>> {
+    let (Ok(width), Ok(height)) = (u16::try_from(image.width), u16::try_from(image.height)) else {
+        return Err(invalid("image is too large"));
+    };
+    if image.indices.len() != width as usize * height as usize {
+        return Err(invalid("index count doesn't match the image size"));
+    }
+    if image.palette.is_empty() || image.palette.len() > image.format.max_colors() {
+        return Err(invalid("palette size doesn't fit the image format"));
+    }
+
+    let mut output = Vec::new();
+
+    match image.format {
+        Format::Linear {
+            unknown_w,
+            unknown_h,
+            flag,
+        } => {
+            output.write_bytes([0; 16])?;
+            output.write_le(unknown_w)?;
+            output.write_le(unknown_h)?;
+            output.write_le(width)?;
+            output.write_le(height)?;
+            output.write_le(flag)?;
+            output.write_le(image.palette.len() as u16)?;
+            output.write_le(32u16)?;
+            output.write_le(0u16)?;
+            write_palette(&mut output, &image.palette, expansion)?;
+            output.write_bytes(&image.indices)?;
+        }
+        Format::Tiled4 | Format::Tiled8 => {
+            if !width.is_multiple_of(TILE_W as u16) || !height.is_multiple_of(TILE_H as u16) {
+                return Err(invalid(&format!(
+                    "image is {width}x{height}, tiled images need dimensions divisible by {TILE_W}"
+                )));
+            }
+
+            let max_colors = image.format.max_colors();
+            if let Some(index) = image.indices.iter().find(|&&i| i as usize >= max_colors) {
+                return Err(invalid(&format!(
+                    "index {index} doesn't fit a {max_colors} color palette"
+                )));
+            }
+
+            let tiled = tile_indices(&image.indices, width as usize, height as usize);
+            let pixel_data = match image.format {
+                Format::Tiled4 => tiled
+                    .chunks_exact(2)
+                    .map(|pair| pair[0] | pair[1] << 4)
+                    .collect(),
+                _ => tiled,
+            };
+
+            // the decoder only knows full palettes
+            let mut palette = image.palette.clone();
+            palette.resize(max_colors, Color::from([0, 0, 0, 0xFF]));
+
+            output.write_le(0u16)?;
+            output.write_le(max_colors as u16)?;
+            output.write_le(width)?;
+            output.write_le(height)?;
+            output.write_le(pixel_data.len() as u32)?;
+            output.write_le(max_colors as u16 * 2)?;
+            output.write_le(0u16)?;
+            write_palette(&mut output, &palette, expansion)?;
+            output.write_bytes(pixel_data)?;
+        }
+    }
+
+    Ok(output)
+}
+
+// indexes in reading order rearranged into 8x8 tiles
+pub(crate) fn tile_indices(indices: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let mut tiled = Vec::with_capacity(indices.len());
+    for tile_y in (0..height).step_by(TILE_H) {
+        for tile_x in (0..width).step_by(TILE_W) {
+            for y in tile_y..tile_y + TILE_H {
+                tiled.extend_from_slice(&indices[y * width + tile_x..][..TILE_W]);
+            }
+        }
+    }
+    tiled
+}
+
+// the inverse of `tile_indices`, tiles missing from the data are left as index 0
+pub(crate) fn untile_indices(tiled: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let tile_row_count = width / TILE_W;
+
+    let mut indices = vec![0; width * height];
+    for (i, tile) in tiled.chunks_exact(TILE_W * TILE_H).enumerate() {
+        let tile_x = (i % tile_row_count) * TILE_W;
+        let tile_y = (i / tile_row_count) * TILE_H;
+        if tile_y >= height {
+            break;
+        }
+
+        for (j, index) in tile.iter().copied().enumerate() {
+            let x = (j % TILE_W) + tile_x;
+            let y = (j / TILE_W) + tile_y;
+            indices[y * width + x] = index;
+        }
+    }
+    indices
+}
+
+fn read_palette(
+    reader: &mut &[u8],
+    count: u16,
+    transparency: Transparency,
+    expansion: Expansion,
+) -> Result<Vec<Color>> {
+    (0..count as usize)
+        .map(|i| Ok(transparency.color(i, reader.read_bytes::<2>()?, expansion)))
+        .collect()
+}
+
+fn write_palette(output: &mut Vec<u8>, palette: &[Color], expansion: Expansion) -> Result<()> {
+    for color in palette {
+        output.write_bytes(color.to_rgb555_with(expansion))?;
+    }
+    Ok(())
+}
+
+impl Format {
+    pub fn max_colors(&self) -> usize {
+        match self {
+            Self::Tiled4 => 16,
+            Self::Linear { .. } | Self::Tiled8 => 256,
+        }
+    }
+}
+
+impl Image {
+    /// an image from rgba8888 pixels, quantized to the rgb555 colors the
+    /// format allows and widened back with `expansion`
+    pub fn from_rgba(
+        width: u32,
+        height: u32,
+        rgba: &[u8],
+        format: Format,
+        expansion: Expansion,
+    ) -> Result<Self> {
+        if rgba.len() != width as usize * height as usize * size_of::<Color>() {
+            return Err(invalid("pixel count doesn't match the image size"));
+        }
+
+        // fully transparent pixels take index 0, the one `Transparency::Index0` hides,
+        // and the other colors share the rest of the palette
+        let transparent = rgba
+            .chunks_exact(size_of::<Color>())
+            .map(|pixel| pixel[3] == 0)
+            .collect::<Vec<_>>();
+        let reserved = transparent.contains(&true) as usize;
+
+        let colors = rgba
+            .chunks_exact(size_of::<Color>())
+            .zip(&transparent)
+            .filter(|&(_, &transparent)| !transparent)
+            .map(|(pixel, _)| {
+                let color = Color::from([pixel[0], pixel[1], pixel[2], 0xFF]);
+                u16::from_le_bytes(color.to_rgb555_with(expansion))
+            })
+            .collect::<Vec<_>>();
+        let (palette, opaque_indices) = quantize(&colors, format.max_colors() - reserved);
+
+        let mut opaque_indices = opaque_indices.into_iter();
+        let indices = transparent
+            .iter()
+            .map(|&transparent| match transparent {
+                true => 0,
+                false => opaque_indices.next().unwrap_or_default() + reserved as u8,
+            })
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            palette: std::iter::repeat_n(Color::from([0; 4]), reserved)
+                .chain(
+                    palette
+                        .into_iter()
+                        .map(|color| Color::from_rgb555_with(color.to_le_bytes(), expansion)),
+                )
+                .collect(),
+            indices,
+            format,
+        })
+    }
+
+    /// the image as rgba8888, indexes past the end of the palette wrap around
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.indices.len() * size_of::<Color>());
+        for &index in &self.indices {
+            let color = self.palette[index as usize % self.palette.len()];
+            rgba.extend_from_slice(color.as_ref());
+        }
+        rgba
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Format, Image, decode};
+    use crate::util::Expansion;
+
+    #[test]
+    fn zero_width_is_rejected() {
+        // tiled header: 16 colors, 0x8 pixels, one tile of pixel data, 32 byte palette
+        let mut data = vec![0, 0, 16, 0, 0, 0, 8, 0, 32, 0, 0, 0, 32, 0, 0, 0];
+        data.resize(data.len() + 32 + 32, 0);
+        assert!(decode(&data).is_err());
+    }
+
+    #[test]
+    fn transparent_pixels_take_index_0() {
+        // one transparent pixel and 63 opaque ones in 16 different colors
+        let rgba = (0..64u8)
+            .flat_map(|i| match i {
+                0 => [0xFF, 0xFF, 0xFF, 0],
+                _ => [(i % 16) * 16, 0, 0xFF, 0xFF],
+            })
+            .collect::<Vec<_>>();
+        let image = Image::from_rgba(8, 8, &rgba, Format::Tiled4, Expansion::default()).unwrap();
+
+        assert!(image.palette.len() <= 16);
+        assert_eq!(image.palette[0].a(), 0);
+        assert_eq!(image.indices[0], 0);
+        assert!(image.indices[1..].iter().all(|&index| index != 0));
+    }
+}
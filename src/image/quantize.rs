@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+/// reduces rgb555 colors to at most `max_colors` with median cut, returning
+/// the palette and the palette index of every input color; `max_colors`
+/// can't be more than 256
+pub fn quantize(colors: &[u16], max_colors: usize) -> (Vec<u16>, Vec<u8>) {
+    if colors.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+
+    let mut counts = HashMap::<u16, usize>::new();
+    for &color in colors {
+        *counts.entry(color).or_default() += 1;
+    }
+
+    let mut unique = counts.into_iter().collect::<Vec<_>>();
+    unique.sort();
+
+    let mut boxes = vec![unique];
+    while boxes.len() < max_colors {
+        // split the box spanning the widest channel range
+        let Some((i, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| {
+                let (channel, range) = (0..3)
+                    .map(|channel| {
+                        let values = colors.iter().map(|&(color, _)| component(color, channel));
+                        let range = values.clone().max().unwrap_or_default()
+                            - values.min().unwrap_or_default();
+                        (channel, range)
+                    })
+                    .max_by_key(|&(_, range)| range)
+                    .unwrap_or_default();
+                (i, channel, range)
+            })
+            .max_by_key(|&(_, _, range)| range)
+            .map(|(i, channel, _)| (i, channel))
+        else {
+            break;
+        };
+
+        let colors = &mut boxes[i];
+        colors.sort_by_key(|&(color, _)| component(color, channel));
+
+        // split at the median pixel, keeping at least one color on each side
+        let total = colors.iter().map(|&(_, count)| count).sum::<usize>();
+        let mut seen = 0;
+        let median = colors
+            .iter()
+            .position(|&(_, count)| {
+                seen += count;
+                seen * 2 >= total
+            })
+            .unwrap_or_default();
+        let upper = colors.split_off((median + 1).min(colors.len() - 1));
+        boxes.push(upper);
+    }
+
+    let mut palette = Vec::with_capacity(boxes.len());
+    let mut lookup = HashMap::new();
+    for (index, colors) in boxes.iter().enumerate() {
+        let total = colors.iter().map(|&(_, count)| count).sum::<usize>();
+        let average = |channel| {
+            let sum = colors
+                .iter()
+                .map(|&(color, count)| component(color, channel) as usize * count)
+                .sum::<usize>();
+            ((sum + total / 2) / total) as u16
+        };
+
+        palette.push(average(0) | average(1) << 5 | average(2) << 10);
+        for &(color, _) in colors {
+            lookup.insert(color, index as u8);
+        }
+    }
+
+    let indices = colors.iter().map(|color| lookup[color]).collect();
+    (palette, indices)
+}
+
+fn component(color: u16, channel: u16) -> u16 {
+    (color >> (channel * 5)) & 0x1F
+}
@@ -1 +1,7 @@
+pub mod cli;
+pub mod font;
+pub mod image;
+pub mod text;
+pub mod txt;
 pub mod util;
+pub mod wpf;
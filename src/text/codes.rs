@@ -0,0 +1,122 @@
+use std::{
+    io::{Error, Result},
+    path::Path,
+};
+
+/// what a control code does, as far as text layout is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeKind {
+    Newline,
+    Speaker,
+    Color,
+    Pause,
+    /// replaced by text at runtime, e.g. the player's name
+    Variable,
+    Other,
+}
+
+impl CodeKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "newline" => Some(Self::Newline),
+            "speaker" => Some(Self::Speaker),
+            "color" => Some(Self::Color),
+            "pause" => Some(Self::Pause),
+            "variable" => Some(Self::Variable),
+            "other" => Some(Self::Other),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CodeDef {
+    pub byte: u8,
+    /// the tag used in markup
+    pub name: String,
+    pub kind: CodeKind,
+    /// number of argument bytes following the code
+    pub args: usize,
+}
+
+/// maps control code bytes to markup tags
+#[derive(Debug, Clone)]
+pub struct CodeTable {
+    defs: Vec<CodeDef>,
+}
+
+impl CodeTable {
+    /// parses one definition per line: `byte name kind args`, e.g. `0x01 speaker speaker 1`,
+    /// empty lines and lines starting with `#` are skipped. definitions are added
+    /// to the default table, replacing any with the same byte
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut defs = Self::default().defs;
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || Error::other(format!("invalid code definition at line {}", i + 1));
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let [byte, name, kind, args] = fields[..] else {
+                return Err(invalid());
+            };
+
+            let byte = parse_byte(byte).ok_or_else(invalid)?;
+            let kind = CodeKind::from_name(kind).ok_or_else(invalid)?;
+            let args = args.parse().map_err(|_| invalid())?;
+            let valid_name = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !name.starts_with("0x");
+
+            if byte >= 0x20 || !valid_name {
+                return Err(invalid());
+            }
+
+            defs.retain(|def: &CodeDef| def.byte != byte && def.name != name);
+            defs.push(CodeDef {
+                byte,
+                name: name.to_string(),
+                kind,
+                args,
+            });
+        }
+
+        Ok(Self { defs })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn by_byte(&self, byte: u8) -> Option<&CodeDef> {
+        self.defs.iter().find(|def| def.byte == byte)
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&CodeDef> {
+        self.defs.iter().find(|def| def.name == name)
+    }
+}
+
+impl Default for CodeTable {
+    /// only the line break is known for sure, every other control byte
+    /// shows up as `{0xNN}` until it is added to a table
+    fn default() -> Self {
+        Self {
+            defs: vec![CodeDef {
+                byte: 0x0A,
+                name: "n".to_string(),
+                kind: CodeKind::Newline,
+                args: 0,
+            }],
+        }
+    }
+}
+
+pub(super) fn parse_byte(text: &str) -> Option<u8> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
@@ -0,0 +1,208 @@
+use encoding_rs::{DecoderResult, EncoderResult, SHIFT_JIS, WINDOWS_1252};
+
+/// character encodings used by the different releases of the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Ascii,
+    /// japanese release
+    ShiftJis,
+    /// european releases, a superset of latin-1 for the printable range
+    Cp1252,
+}
+
+impl Encoding {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ascii" => Some(Self::Ascii),
+            "sjis" | "shift-jis" | "shift_jis" => Some(Self::ShiftJis),
+            "cp1252" | "windows-1252" | "latin1" | "latin-1" => Some(Self::Cp1252),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Ascii => "ascii",
+            Self::ShiftJis => "sjis",
+            Self::Cp1252 => "cp1252",
+        }
+    }
+
+    /// guesses the encoding of raw game text
+    pub fn detect<L: AsRef<[u8]>>(lines: &[L]) -> Self {
+        let lines = || lines.iter().map(|line| line.as_ref());
+
+        if lines().all(|line| line.is_ascii()) {
+            return Self::Ascii;
+        }
+
+        // accented latin letters often form valid shift-jis too, capitals like
+        // Ü or À even decode to halfwidth katakana on their own, so only kana,
+        // kanji and full width ascii count as japanese
+        let mut japanese = false;
+        for line in lines() {
+            let (text, errors) = Self::ShiftJis.decode(line);
+            if !errors.is_empty() {
+                return Self::Cp1252;
+            }
+
+            // hiragana and katakana, cjk ideographs, full width ascii
+            japanese |= text.chars().any(|c| {
+                matches!(
+                    c,
+                    '\u{3040}'..='\u{30FF}' | '\u{4E00}'..='\u{9FFF}' | '\u{FF01}'..='\u{FF5E}'
+                )
+            });
+        }
+
+        if japanese {
+            Self::ShiftJis
+        } else {
+            Self::Cp1252
+        }
+    }
+
+    /// picks the first encoding able to represent all of the text,
+    /// trying cp1252 first since japanese text hardly ever fits in it
+    pub fn detect_for_text<L: AsRef<str>>(lines: &[L]) -> Option<Self> {
+        [Self::Ascii, Self::Cp1252, Self::ShiftJis]
+            .into_iter()
+            .find(|encoding| {
+                lines
+                    .iter()
+                    .all(|line| encoding.encode(line.as_ref()).1.is_empty())
+            })
+    }
+
+    /// the encoding to write translated text in: `source`, the encoding of the
+    /// original file, while all of `lines` fit in it, otherwise the first
+    /// encoding that fits them. an english original is plain ascii, the
+    /// translation may need more
+    pub fn for_translation<L: AsRef<str>>(source: Option<Self>, lines: &[L]) -> Option<Self> {
+        let fits = |encoding: Self| {
+            lines
+                .iter()
+                .all(|line| encoding.encode(line.as_ref()).1.is_empty())
+        };
+
+        match source {
+            Some(source) if fits(source) => Some(source),
+            _ => Self::detect_for_text(lines),
+        }
+    }
+
+    /// decodes to utf-8, returning the positions of the bytes that couldn't be decoded
+    /// which are replaced with U+FFFD
+    pub fn decode(self, bytes: &[u8]) -> (String, Vec<usize>) {
+        let encoding = match self {
+            Self::Ascii => {
+                let errors = (0..bytes.len()).filter(|&i| !bytes[i].is_ascii()).collect();
+                let text = bytes
+                    .iter()
+                    .map(|&b| if b.is_ascii() { b as char } else { '\u{FFFD}' })
+                    .collect();
+                return (text, errors);
+            }
+            Self::ShiftJis => SHIFT_JIS,
+            Self::Cp1252 => WINDOWS_1252,
+        };
+
+        let mut decoder = encoding.new_decoder_without_bom_handling();
+        let mut text = String::with_capacity(bytes.len() * 3);
+        let mut errors = Vec::new();
+        let mut pos = 0;
+
+        loop {
+            let (result, read) =
+                decoder.decode_to_string_without_replacement(&bytes[pos..], &mut text, true);
+            pos += read;
+
+            match result {
+                DecoderResult::InputEmpty => break,
+                DecoderResult::OutputFull => text.reserve(bytes.len() - pos + 16),
+                DecoderResult::Malformed(bad_len, extra_len) => {
+                    errors.push(pos - bad_len as usize - extra_len as usize);
+                    text.push('\u{FFFD}');
+                }
+            }
+        }
+
+        (text, errors)
+    }
+
+    /// encodes utf-8 text, returning the characters that have no mapping
+    /// which are replaced with '?'
+    pub fn encode(self, text: &str) -> (Vec<u8>, Vec<char>) {
+        let encoding = match self {
+            Self::Ascii => {
+                let errors = text.chars().filter(|c| !c.is_ascii()).collect();
+                let bytes = text
+                    .chars()
+                    .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+                    .collect();
+                return (bytes, errors);
+            }
+            Self::ShiftJis => SHIFT_JIS,
+            Self::Cp1252 => WINDOWS_1252,
+        };
+
+        let mut encoder = encoding.new_encoder();
+        let mut bytes = Vec::with_capacity(text.len() * 2);
+        let mut errors = Vec::new();
+        let mut pos = 0;
+
+        loop {
+            let (result, read) =
+                encoder.encode_from_utf8_to_vec_without_replacement(&text[pos..], &mut bytes, true);
+            pos += read;
+
+            match result {
+                EncoderResult::InputEmpty => break,
+                EncoderResult::OutputFull => bytes.reserve(text.len() - pos + 16),
+                EncoderResult::Unmappable(c) => {
+                    errors.push(c);
+                    bytes.push(b'?');
+                }
+            }
+        }
+
+        (bytes, errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Encoding;
+
+    fn detect(lines: &[&str], encoding: Encoding) -> Encoding {
+        let lines = lines
+            .iter()
+            .map(|line| encoding.encode(line).0)
+            .collect::<Vec<_>>();
+        Encoding::detect(&lines)
+    }
+
+    #[test]
+    fn german() {
+        let lines = ["Über dem Tisch.", "Schön!", "Das Straßenschild."];
+        assert_eq!(detect(&lines, Encoding::Cp1252), Encoding::Cp1252);
+    }
+
+    #[test]
+    fn french() {
+        let lines = ["Ça va.", "À bientôt."];
+        assert_eq!(detect(&lines, Encoding::Cp1252), Encoding::Cp1252);
+    }
+
+    #[test]
+    fn japanese() {
+        let lines = ["ホテルダスク", "今日は晴れ。", "ＯＫ"];
+        assert_eq!(detect(&lines, Encoding::ShiftJis), Encoding::ShiftJis);
+        assert_eq!(detect(&["ＯＫ"], Encoding::ShiftJis), Encoding::ShiftJis);
+    }
+
+    #[test]
+    fn ascii() {
+        assert_eq!(detect(&["Hello."], Encoding::Ascii), Encoding::Ascii);
+    }
+}
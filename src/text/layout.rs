@@ -0,0 +1,119 @@
+use super::{CodeKind, CodeTable, Line, Token};
+use std::{
+    collections::HashMap,
+    io::{Error, Result},
+    path::Path,
+};
+
+/// advance width of every glyph in pixels
+#[derive(Debug, Clone, Default)]
+pub struct WidthTable {
+    widths: HashMap<char, u32>,
+}
+
+impl WidthTable {
+    /// parses `char width` pairs, one per line. the character can also be written as
+    /// `U+XXXX`, which is needed for `#` since lines starting with it are skipped.
+    /// columns after the width are ignored, as are rows with an empty character
+    /// column, which font2png writes for codes that have no character
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut widths = HashMap::new();
+
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with(['#', '\t']) {
+                continue;
+            }
+
+            let invalid = || Error::other(format!("invalid width definition at line {}", i + 1));
+            let (c, rest) = match line.get(..2) {
+                Some("U+" | "u+") => {
+                    let (code_point, rest) =
+                        line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+                    let code_point = u32::from_str_radix(&code_point[2..], 16).ok();
+                    (code_point.and_then(char::from_u32), rest)
+                }
+                // a space is a valid character too, so take the first one as is
+                _ => {
+                    let mut chars = line.chars();
+                    (chars.next(), chars.as_str())
+                }
+            };
+
+            let c = c.ok_or_else(invalid)?;
+            let width = rest.split_whitespace().next().ok_or_else(invalid)?;
+            let width = width.parse().map_err(|_| invalid())?;
+            widths.insert(c, width);
+        }
+
+        Ok(Self { widths })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn insert(&mut self, c: char, width: u32) {
+        self.widths.insert(c, width);
+    }
+
+    pub fn get(&self, c: char) -> Option<u32> {
+        self.widths.get(&c).copied()
+    }
+}
+
+/// settings used to measure a line
+#[derive(Debug, Clone)]
+pub struct Metrics<'a> {
+    pub widths: &'a WidthTable,
+    pub codes: &'a CodeTable,
+    /// width of characters missing from the table
+    pub default_width: u32,
+    /// width assumed for text inserted by variable codes
+    pub variable_width: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Measurement {
+    /// width of each row, rows are separated by newline codes
+    pub rows: Vec<u32>,
+    /// characters that had no width in the table
+    pub missing: Vec<char>,
+}
+
+pub fn measure(line: &Line, metrics: &Metrics) -> Measurement {
+    let mut measurement = Measurement {
+        rows: vec![0],
+        missing: Vec::new(),
+    };
+
+    for token in &line.tokens {
+        let row = measurement.rows.last_mut().unwrap();
+
+        match token {
+            Token::Text(text) => {
+                for c in text.chars() {
+                    // a raw line break is the same byte as the newline code
+                    if c == '\n' {
+                        measurement.rows.push(0);
+                        continue;
+                    }
+
+                    let row = measurement.rows.last_mut().unwrap();
+                    *row += metrics.widths.get(c).unwrap_or_else(|| {
+                        if !measurement.missing.contains(&c) {
+                            measurement.missing.push(c);
+                        }
+                        metrics.default_width
+                    });
+                }
+            }
+            Token::Control { code, .. } => match metrics.codes.by_byte(*code).map(|def| def.kind) {
+                Some(CodeKind::Newline) => measurement.rows.push(0),
+                Some(CodeKind::Variable) => *row += metrics.variable_width,
+                _ => {}
+            },
+        }
+    }
+
+    measurement
+}
@@ -0,0 +1,271 @@
+use super::{CodeTable, Encoding, codes::parse_byte};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Text(String),
+    /// a control byte and the argument bytes following it
+    Control {
+        code: u8,
+        args: Vec<u8>,
+    },
+}
+
+/// a line of game text split into text runs and control codes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Line {
+    pub tokens: Vec<Token>,
+}
+
+#[derive(Debug)]
+pub enum MarkupError {
+    UnclosedTag(usize),
+    UnmatchedBrace(usize),
+    UnknownTag(String),
+    /// the number of arguments doesn't match the code definition
+    BadArguments(String),
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedTag(pos) => write!(f, "unclosed tag at {pos}"),
+            Self::UnmatchedBrace(pos) => write!(f, "unmatched '}}' at {pos}, use '}}}}'"),
+            Self::UnknownTag(tag) => write!(f, "unknown tag {{{tag}}}"),
+            Self::BadArguments(tag) => write!(f, "wrong arguments in {{{tag}}}"),
+        }
+    }
+}
+
+impl std::error::Error for MarkupError {}
+
+impl Line {
+    /// splits raw game text, returning the positions of bytes that couldn't be decoded
+    pub fn decode(bytes: &[u8], encoding: Encoding, codes: &CodeTable) -> (Self, Vec<usize>) {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        let mut text_start = 0;
+        let mut pos = 0;
+
+        let mut push_text = |tokens: &mut Vec<Token>, start: usize, end: usize| {
+            if start < end {
+                let (text, text_errors) = encoding.decode(&bytes[start..end]);
+                errors.extend(text_errors.into_iter().map(|err| start + err));
+                tokens.push(Token::Text(text));
+            }
+        };
+
+        while pos < bytes.len() {
+            let byte = bytes[pos];
+
+            // control bytes never appear inside multi byte characters
+            if byte >= 0x20 {
+                pos += 1;
+                continue;
+            }
+
+            push_text(&mut tokens, text_start, pos);
+
+            let arg_count = codes.by_byte(byte).map_or(0, |def| def.args);
+            let args_end = (pos + 1 + arg_count).min(bytes.len());
+            tokens.push(Token::Control {
+                code: byte,
+                args: bytes[pos + 1..args_end].to_vec(),
+            });
+
+            pos = args_end;
+            text_start = pos;
+        }
+
+        push_text(&mut tokens, text_start, pos);
+
+        (Self { tokens }, errors)
+    }
+
+    /// returns the raw bytes and the characters that have no mapping in the encoding
+    pub fn encode(&self, encoding: Encoding) -> (Vec<u8>, Vec<char>) {
+        let mut bytes = Vec::new();
+        let mut errors = Vec::new();
+
+        for token in &self.tokens {
+            match token {
+                Token::Text(text) => {
+                    let (text_bytes, text_errors) = encoding.encode(text);
+                    bytes.extend(text_bytes);
+                    errors.extend(text_errors);
+                }
+                Token::Control { code, args } => {
+                    bytes.push(*code);
+                    bytes.extend(args);
+                }
+            }
+        }
+
+        (bytes, errors)
+    }
+
+    /// renders the line with `{tag}` markup, literal braces are doubled
+    pub fn to_markup(&self, codes: &CodeTable) -> String {
+        let mut markup = String::new();
+
+        for token in &self.tokens {
+            match token {
+                Token::Text(text) => {
+                    markup.push_str(&text.replace('{', "{{").replace('}', "}}"));
+                }
+                Token::Control { code, args } => {
+                    markup.push('{');
+                    // codes cut short by the end of the line fall back to the raw form
+                    match codes.by_byte(*code) {
+                        Some(def) if def.args == args.len() => markup.push_str(&def.name),
+                        _ => markup.push_str(&format!("0x{code:02X}")),
+                    }
+
+                    for (i, arg) in args.iter().enumerate() {
+                        markup.push(if i == 0 { ':' } else { ',' });
+                        markup.push_str(&arg.to_string());
+                    }
+                    markup.push('}');
+                }
+            }
+        }
+
+        markup
+    }
+
+    pub fn from_markup(markup: &str, codes: &CodeTable) -> Result<Self, MarkupError> {
+        let mut tokens = Vec::new();
+        let mut text = String::new();
+        let mut chars = markup.char_indices().peekable();
+
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|&(_, c)| c == '{').is_some() => text.push('{'),
+                '}' if chars.next_if(|&(_, c)| c == '}').is_some() => text.push('}'),
+                '}' => return Err(MarkupError::UnmatchedBrace(pos)),
+                '{' => {
+                    let Some(len) = markup[pos + 1..].find('}') else {
+                        return Err(MarkupError::UnclosedTag(pos));
+                    };
+                    let tag = &markup[pos + 1..][..len];
+                    for _ in 0..=len {
+                        chars.next();
+                    }
+
+                    if !text.is_empty() {
+                        tokens.push(Token::Text(std::mem::take(&mut text)));
+                    }
+                    tokens.push(parse_tag(tag, codes)?);
+                }
+                c => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            tokens.push(Token::Text(text));
+        }
+
+        Ok(Self { tokens })
+    }
+}
+
+fn parse_tag(tag: &str, codes: &CodeTable) -> Result<Token, MarkupError> {
+    let (name, args) = match tag.split_once(':') {
+        Some((name, args)) => (name, args.split(',').collect::<Vec<_>>()),
+        None => (tag, Vec::new()),
+    };
+
+    let bad_arguments = || MarkupError::BadArguments(tag.to_string());
+    let args = args
+        .iter()
+        .map(|arg| parse_byte(arg.trim()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(bad_arguments)?;
+
+    let code = match codes.by_name(name) {
+        Some(def) if def.args != args.len() => return Err(bad_arguments()),
+        Some(def) => def.byte,
+        // the raw form skips the argument check
+        None => match name.strip_prefix("0x").and_then(|_| parse_byte(name)) {
+            Some(byte) if byte < 0x20 => byte,
+            _ => return Err(MarkupError::UnknownTag(tag.to_string())),
+        },
+    };
+
+    Ok(Token::Control { code, args })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Line, MarkupError, Token};
+    use crate::text::{CodeTable, Encoding};
+
+    fn codes() -> CodeTable {
+        CodeTable::parse("0x01 speaker speaker 1\n0x02 color color 2").unwrap()
+    }
+
+    fn round_trip(bytes: &[u8], markup: &str, codes: &CodeTable) {
+        let (line, errors) = Line::decode(bytes, Encoding::Ascii, codes);
+        assert!(errors.is_empty());
+        assert_eq!(line.to_markup(codes), markup);
+
+        let parsed = Line::from_markup(markup, codes).unwrap();
+        assert_eq!(parsed, line);
+        assert_eq!(parsed.encode(Encoding::Ascii).0, bytes);
+    }
+
+    #[test]
+    fn markup_round_trip() {
+        round_trip(
+            b"\x01\x03Hello\x02\x01\x02 you\x0Athere",
+            "{speaker:3}Hello{color:1,2} you{n}there",
+            &codes(),
+        );
+    }
+
+    #[test]
+    fn escaped_braces() {
+        let codes = codes();
+        round_trip(b"a {b} }{ c", "a {{b}} }}{{ c", &codes);
+
+        assert!(matches!(
+            Line::from_markup("a } b", &codes),
+            Err(MarkupError::UnmatchedBrace(2))
+        ));
+        assert!(matches!(
+            Line::from_markup("a {n", &codes),
+            Err(MarkupError::UnclosedTag(2))
+        ));
+    }
+
+    #[test]
+    fn unknown_codes() {
+        let codes = codes();
+        // unknown bytes take no arguments and keep their raw form
+        round_trip(b"a\x07b\x1F", "a{0x07}b{0x1F}", &codes);
+        // as do known codes cut short by the end of the line
+        round_trip(b"a\x02\x01", "a{0x02:1}", &codes);
+
+        let line = Line::from_markup("{0x07:1,2}", &codes).unwrap();
+        assert_eq!(
+            line.tokens,
+            [Token::Control {
+                code: 0x07,
+                args: vec![1, 2]
+            }]
+        );
+
+        assert!(matches!(
+            Line::from_markup("{bogus}", &codes),
+            Err(MarkupError::UnknownTag(_))
+        ));
+        assert!(matches!(
+            Line::from_markup("{0x20}", &codes),
+            Err(MarkupError::UnknownTag(_))
+        ));
+        assert!(matches!(
+            Line::from_markup("{speaker}", &codes),
+            Err(MarkupError::BadArguments(_))
+        ));
+    }
+}
@@ -0,0 +1,7 @@
+mod codes;
+pub use codes::{CodeDef, CodeKind, CodeTable};
+mod encoding;
+pub use encoding::Encoding;
+pub mod layout;
+mod line;
+pub use line::{Line, MarkupError, Token};
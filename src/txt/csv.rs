@@ -0,0 +1,118 @@
+use std::io::{Error, Result, Write};
+
+/// writes one record, quoting fields that contain the delimiter, quotes or line breaks
+pub fn write_record<W: Write, F: AsRef<str>>(
+    writer: &mut W,
+    fields: &[F],
+    delimiter: char,
+) -> Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        let field = field.as_ref();
+
+        if i > 0 {
+            write!(writer, "{delimiter}")?;
+        }
+
+        if field.contains([delimiter, '"', '\n', '\r']) {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            write!(writer, "{field}")?;
+        }
+    }
+
+    writeln!(writer)
+}
+
+/// parses every record, line breaks inside quoted fields are kept as `\n`
+/// and blank lines are skipped
+pub fn parse(text: &str, delimiter: char) -> Result<Vec<Vec<String>>> {
+    let text = text.strip_prefix('\u{FEFF}').unwrap_or(text);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    // a line holding only `""` is a record with one empty field, not a blank line
+    let mut was_quoted = false;
+    let mut line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.next_if_eq(&'"').is_some() {
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => {
+                quoted = true;
+                was_quoted = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                line += 1;
+
+                if quoted {
+                    field.push('\n');
+                } else if !record.is_empty() || !field.is_empty() || was_quoted {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                    was_quoted = false;
+                }
+            }
+            c if c == delimiter && !quoted => {
+                record.push(std::mem::take(&mut field));
+                was_quoted = false;
+            }
+            c => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(Error::other(format!(
+            "unterminated quoted field at line {line}"
+        )));
+    }
+
+    if !field.is_empty() || !record.is_empty() || was_quoted {
+        record.push(field);
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting() {
+        let fields = ["plain", "with,comma", "say \"hi\"", ""];
+        let mut text = Vec::new();
+        write_record(&mut text, &fields, ',').unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert_eq!(text, "plain,\"with,comma\",\"say \"\"hi\"\"\",\n");
+        assert_eq!(parse(&text, ',').unwrap(), [fields]);
+
+        assert!(parse("a,\"open\n", ',').is_err());
+    }
+
+    #[test]
+    fn embedded_newlines() {
+        let text = "1\t\"first\r\nsecond\"\r\n2\tthird\r\n";
+        let records = parse(text, '\t').unwrap();
+        assert_eq!(records, [["1", "first\nsecond"], ["2", "third"]]);
+    }
+
+    #[test]
+    fn blank_lines() {
+        let text = "\u{FEFF}a,b\n\nc,d\n\n\"\"\n\n";
+        let records = parse(text, ',').unwrap();
+        assert_eq!(records, [vec!["a", "b"], vec!["c", "d"], vec![""]]);
+
+        assert_eq!(parse("a\nb", ',').unwrap(), [["a"], ["b"]]);
+        assert!(parse("\n\r\n", ',').unwrap().is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+pub mod csv;
+pub mod po;
+
+use crate::util::{ReadExt, WriteExt};
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+
+/// reads the line table and returns every line without its null terminator
+pub fn read_lines<R: Read + Seek>(reader: &mut R) -> Result<Vec<Vec<u8>>> {
+    let len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    let lines_count: u32 = reader.read_le()?;
+    let lines_start = 4 + lines_count as u64 * 4;
+
+    if lines_start > len {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "line table is larger than the file",
+        ));
+    }
+
+    let mut buf = vec![0; 4 * lines_count as usize];
+    reader.read_exact(&mut buf)?;
+    let mut lines_table = buf.as_slice();
+
+    let mut buf_reader = BufReader::new(reader);
+    let mut lines = Vec::with_capacity(lines_count as usize);
+    for _ in 0..lines_count {
+        let mut line = Vec::new();
+        let offset = lines_start + lines_table.read_le::<u32>()? as u64;
+
+        buf_reader.seek(SeekFrom::Start(offset))?;
+        buf_reader.read_until(0, &mut line)?;
+
+        // remove null byte
+        if line.last() == Some(&0) {
+            line.pop();
+        }
+        lines.push(line);
+    }
+
+    Ok(lines)
+}
+
+/// writes the line count, the offset table and the null terminated lines
+pub fn write_lines<W: Write, L: AsRef<[u8]>>(writer: &mut W, lines: &[L]) -> Result<()> {
+    let Ok(lines_count) = u32::try_from(lines.len()) else {
+        return Err(Error::other("too many lines"));
+    };
+
+    writer.write_le(lines_count)?;
+
+    // offsets are relative to the end of the table
+    let mut offset = 0u32;
+    for line in lines {
+        let line = line.as_ref();
+        if line.contains(&0) {
+            return Err(Error::other("line contains a null byte"));
+        }
+
+        writer.write_le(offset)?;
+        offset = u32::try_from(line.len() + 1)
+            .ok()
+            .and_then(|len| offset.checked_add(len))
+            .ok_or_else(|| Error::other("text too large"))?;
+    }
+
+    for line in lines {
+        writer.write_bytes(line)?;
+        writer.write_le(0u8)?;
+    }
+
+    Ok(())
+}
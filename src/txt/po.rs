@@ -0,0 +1,252 @@
+use std::io::{Error, Result, Write};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoEntry {
+    pub context: String,
+    pub id: String,
+    pub translation: String,
+    pub fuzzy: bool,
+}
+
+/// the context used for a line, `file_name:index`
+pub fn context(file_name: &str, index: usize) -> String {
+    format!("{file_name}:{index}")
+}
+
+pub fn parse_context(context: &str) -> Option<(&str, usize)> {
+    let (file_name, index) = context.rsplit_once(':')?;
+    Some((file_name, index.parse().ok()?))
+}
+
+pub fn write_po<W: Write>(writer: &mut W, entries: &[PoEntry]) -> Result<()> {
+    writeln!(writer, "msgid \"\"")?;
+    writeln!(writer, "msgstr \"\"")?;
+    writeln!(writer, "\"MIME-Version: 1.0\\n\"")?;
+    writeln!(writer, "\"Content-Type: text/plain; charset=UTF-8\\n\"")?;
+    writeln!(writer, "\"Content-Transfer-Encoding: 8bit\\n\"")?;
+
+    for entry in entries {
+        writeln!(writer)?;
+        if entry.fuzzy {
+            writeln!(writer, "#, fuzzy")?;
+        }
+        writeln!(writer, "msgctxt \"{}\"", escape(&entry.context))?;
+        writeln!(writer, "msgid \"{}\"", escape(&entry.id))?;
+        writeln!(writer, "msgstr \"{}\"", escape(&entry.translation))?;
+    }
+
+    Ok(())
+}
+
+/// parses the entries of a po file, skipping the header
+pub fn parse_po(text: &str) -> Result<Vec<PoEntry>> {
+    #[derive(Clone, Copy)]
+    enum Field {
+        Context,
+        Id,
+        Translation,
+        Other,
+    }
+
+    let mut entries = Vec::new();
+    let mut entry = PoEntry::default();
+    let mut field = None;
+
+    let mut finish = |entry: &mut PoEntry, field: &mut Option<Field>| {
+        let entry = std::mem::take(entry);
+        if field.take().is_some() && !entry.id.is_empty() {
+            entries.push(entry);
+        }
+    };
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let invalid = || Error::other(format!("invalid po syntax at line {}", i + 1));
+
+        if line.is_empty() {
+            finish(&mut entry, &mut field);
+            continue;
+        }
+
+        if let Some(flags) = line.strip_prefix("#,") {
+            if field.is_some() {
+                finish(&mut entry, &mut field);
+            }
+            entry.fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            continue;
+        }
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, rest) = match line.split_once(char::is_whitespace) {
+            Some((keyword, rest)) if !line.starts_with('"') => (Some(keyword), rest.trim()),
+            _ => (None, line),
+        };
+
+        let new_field = match keyword {
+            Some("msgctxt") => Field::Context,
+            Some("msgid") => Field::Id,
+            Some("msgstr" | "msgstr[0]") => Field::Translation,
+            Some(_) => Field::Other,
+            None => field.ok_or_else(invalid)?,
+        };
+
+        // a msgctxt or a msgid after a msgstr starts a new entry
+        if let (Some(Field::Translation), Field::Context | Field::Id) = (field, new_field) {
+            finish(&mut entry, &mut field);
+        }
+
+        let value = unescape(rest).ok_or_else(invalid)?;
+        match new_field {
+            Field::Context => entry.context.push_str(&value),
+            Field::Id => entry.id.push_str(&value),
+            Field::Translation => entry.translation.push_str(&value),
+            Field::Other => {}
+        }
+        field = Some(new_field);
+    }
+
+    finish(&mut entry, &mut field);
+    Ok(entries)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn unescape(quoted: &str) -> Option<String> {
+    let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+
+        match chars.next()? {
+            'n' => text.push('\n'),
+            'r' => text.push('\r'),
+            't' => text.push('\t'),
+            c @ ('"' | '\\') => text.push(c),
+            _ => return None,
+        }
+    }
+
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaping() {
+        let text = "say \"hi\"\\\n\tbye\r";
+        let escaped = escape(text);
+        assert_eq!(escaped, "say \\\"hi\\\"\\\\\\n\\tbye\\r");
+        assert_eq!(unescape(&format!("\"{escaped}\"")).as_deref(), Some(text));
+
+        assert_eq!(unescape("\"\\q\""), None);
+        assert_eq!(unescape("\"trailing\\\""), None);
+        assert_eq!(unescape("unquoted"), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let entries = vec![
+            PoEntry {
+                context: context("a.txt", 0),
+                id: "line \"one\"\nand two".to_string(),
+                translation: "ligne\tun".to_string(),
+                fuzzy: false,
+            },
+            PoEntry {
+                context: context("a.txt", 1),
+                id: "untranslated".to_string(),
+                translation: String::new(),
+                fuzzy: true,
+            },
+        ];
+
+        let mut po = Vec::new();
+        write_po(&mut po, &entries).unwrap();
+        let parsed = parse_po(std::str::from_utf8(&po).unwrap()).unwrap();
+        assert_eq!(parsed, entries);
+        assert_eq!(parse_context(&parsed[1].context), Some(("a.txt", 1)));
+    }
+
+    #[test]
+    fn fuzzy_entries() {
+        let po = r#"
+#, fuzzy
+msgctxt "a.txt:0"
+msgid "one"
+msgstr "un"
+
+#: a.txt
+#, c-format, fuzzy
+msgctxt "a.txt:1"
+msgid "two"
+msgstr "deux"
+
+#, c-format
+msgctxt "a.txt:2"
+msgid "three"
+msgstr "trois"
+"#;
+        let entries = parse_po(po).unwrap();
+        let fuzzy = entries.iter().map(|entry| entry.fuzzy).collect::<Vec<_>>();
+        assert_eq!(fuzzy, [true, true, false]);
+    }
+
+    #[test]
+    fn multi_line_strings() {
+        let po = r#"
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+
+msgctxt "a.txt:"
+"3"
+msgid ""
+"first line\n"
+"second line"
+msgstr "première\n"
+"deuxième"
+msgctxt "a.txt:4"
+msgid "next"
+msgstr ""
+"suivant"
+"#;
+        let entries = parse_po(po).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].context, "a.txt:3");
+        assert_eq!(entries[0].id, "first line\nsecond line");
+        assert_eq!(entries[0].translation, "première\ndeuxième");
+        assert_eq!(entries[1].id, "next");
+        assert_eq!(entries[1].translation, "suivant");
+    }
+
+    #[test]
+    fn invalid_syntax() {
+        assert!(parse_po("\"orphan\"").is_err());
+        assert!(parse_po("msgid \"unterminated").is_err());
+    }
+}
@@ -2,20 +2,119 @@
 #[derive(Clone, Copy)]
 pub struct Color([u8; 4]);
 
+/// how 5-bit color channels are widened to 8 bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Expansion {
+    /// the top bits repeated in the low bits, `x << 3 | x >> 2`
+    #[default]
+    BitReplication,
+    /// widened to 6 bits like the DS does before the LCD, then to 8 bits
+    Hardware,
+    /// `x * 255 / 31` rounded
+    Linear,
+}
+
+impl Expansion {
+    /// `replicate`, `hardware` or `linear`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "replicate" => Some(Self::BitReplication),
+            "hardware" => Some(Self::Hardware),
+            "linear" => Some(Self::Linear),
+            _ => None,
+        }
+    }
+
+    pub fn expand(&self, x: u8) -> u8 {
+        let x = x & 0x1F;
+        match self {
+            Self::BitReplication => x << 3 | x >> 2,
+            Self::Hardware => {
+                // every non zero channel gets its lowest 6-bit step lit
+                let x = x << 1 | (x != 0) as u8;
+                x << 2 | x >> 4
+            }
+            Self::Linear => ((x as u16 * 0xFF + 15) / 31) as u8,
+        }
+    }
+
+    /// the 5-bit value that expands closest to `c`
+    pub fn reduce(&self, c: u8) -> u8 {
+        (0..32)
+            .min_by_key(|&x| (self.expand(x) as i16 - c as i16).abs())
+            .unwrap_or_default()
+    }
+}
+
+/// which palette colors are drawn transparent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Transparency {
+    #[default]
+    Opaque,
+    /// the first palette color, the background of most sprites
+    Index0,
+    /// colors without bit 15 set, as in the DS direct color format
+    AlphaBit,
+    /// every color with this rgb555 value
+    Key(u16),
+}
+
+impl Transparency {
+    /// `none`, `index0`, `bit15` or an rgb555 value such as `0x7C1F`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Self::Opaque),
+            "index0" => Some(Self::Index0),
+            "bit15" => Some(Self::AlphaBit),
+            _ => name
+                .strip_prefix("0x")
+                .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+                .map(|key| Self::Key(key & 0x7FFF)),
+        }
+    }
+
+    /// the palette color at `index` with the rule applied
+    pub fn color(&self, index: usize, bytes: [u8; 2], expansion: Expansion) -> Color {
+        let word = u16::from_le_bytes(bytes);
+        let transparent = match *self {
+            Self::Opaque => false,
+            Self::Index0 => index == 0,
+            Self::AlphaBit => word & 0x8000 == 0,
+            Self::Key(key) => word & 0x7FFF == key,
+        };
+
+        let color = Color::from_rgb555_with(bytes, expansion);
+        match transparent {
+            true => Color([color.r(), color.g(), color.b(), 0]),
+            false => color,
+        }
+    }
+}
+
 impl Color {
     pub fn from_rgb555(bytes: [u8; 2]) -> Self {
-        let mut color = [0xFF; 4];
+        Self::from_rgb555_with(bytes, Expansion::default())
+    }
+
+    pub fn from_rgb555_with(bytes: [u8; 2], expansion: Expansion) -> Self {
         let word = u16::from_le_bytes(bytes);
+        let channel = |shift: u16| expansion.expand(((word >> shift) & 0x1F) as u8);
 
-        color[0] = (word & 0x1F) as u8 * 8;
-        color[1] = ((word >> 5) & 0x1F) as u8 * 8;
-        color[2] = ((word >> 10) & 0x1F) as u8 * 8;
+        Self([channel(0), channel(5), channel(10), 0xFF])
+    }
 
-        color[0] += color[0] / 32;
-        color[1] += color[1] / 32;
-        color[2] += color[2] / 32;
+    /// the nearest rgb555 color, colors made by [`Color::from_rgb555`] come back unchanged
+    pub fn to_rgb555(&self) -> [u8; 2] {
+        self.to_rgb555_with(Expansion::default())
+    }
 
-        Self(color)
+    /// the nearest rgb555 color, colors made by [`Color::from_rgb555_with`]
+    /// with the same expansion come back unchanged
+    pub fn to_rgb555_with(&self, expansion: Expansion) -> [u8; 2] {
+        let word = expansion.reduce(self.r()) as u16
+            | (expansion.reduce(self.g()) as u16) << 5
+            | (expansion.reduce(self.b()) as u16) << 10;
+        word.to_le_bytes()
     }
 
     pub fn r(&self) -> u8 {
@@ -57,3 +156,23 @@ impl AsRef<[u8]> for Color {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Color, Expansion};
+
+    #[test]
+    fn rgb555_round_trips() {
+        for expansion in [
+            Expansion::BitReplication,
+            Expansion::Hardware,
+            Expansion::Linear,
+        ] {
+            for word in 0..0x8000u16 {
+                let color = Color::from_rgb555_with(word.to_le_bytes(), expansion);
+                let back = u16::from_le_bytes(color.to_rgb555_with(expansion));
+                assert_eq!(back, word, "{expansion:?}");
+            }
+        }
+    }
+}
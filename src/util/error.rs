@@ -0,0 +1,68 @@
+use std::{fmt, io};
+
+#[derive(Debug)]
+pub enum DecompressError {
+    /// the magic doesn't match any known compression method
+    UnknownMagic([u8; 4]),
+    /// the input ended before the header was complete
+    TruncatedHeader,
+    /// the input ended before the declared amount of data was produced
+    TruncatedStream {
+        expected: usize,
+        actual: usize,
+    },
+    Io(io::Error),
+}
+
+impl DecompressError {
+    fn from_io(err: io::Error, eof: Self) -> Self {
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => eof,
+            _ => Self::Io(err),
+        }
+    }
+
+    pub(crate) fn header(err: io::Error) -> Self {
+        Self::from_io(err, Self::TruncatedHeader)
+    }
+
+    pub(crate) fn stream(err: io::Error, expected: usize, actual: usize) -> Self {
+        Self::from_io(err, Self::TruncatedStream { expected, actual })
+    }
+}
+
+impl fmt::Display for DecompressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownMagic(magic) => {
+                write!(f, "uncompressed or unknown compression method {magic:02X?}")
+            }
+            Self::TruncatedHeader => write!(f, "truncated compression header"),
+            Self::TruncatedStream { expected, actual } => {
+                write!(f, "truncated stream, got {actual} of {expected} bytes")
+            }
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for DecompressError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecompressError> for io::Error {
+    fn from(value: DecompressError) -> Self {
+        match value {
+            DecompressError::Io(err) => err,
+            DecompressError::TruncatedHeader | DecompressError::TruncatedStream { .. } => {
+                io::Error::new(io::ErrorKind::UnexpectedEof, value)
+            }
+            _ => io::Error::new(io::ErrorKind::InvalidData, value),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use super::{DecompressError, ReadExt};
+use std::io::Read;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionMethod {
+    Stored,
+    Lzss,
+    Rle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionInfo {
+    pub method: CompressionMethod,
+    pub header_size: usize,
+    pub decompressed_size: usize,
+    /// size of the data following the header, `None` when the header doesn't declare it
+    pub compressed_size: Option<usize>,
+}
+
+impl CompressionInfo {
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, DecompressError> {
+        let magic = reader.read_bytes::<4>().map_err(DecompressError::header)?;
+        Self::from_magic(reader, magic)
+    }
+
+    pub(crate) fn from_magic<R: Read>(
+        reader: &mut R,
+        magic: [u8; 4],
+    ) -> Result<Self, DecompressError> {
+        match magic {
+            [0x12, 0x3D, 0xDA, flag @ 0..=1] => {
+                let dst_size: u32 = reader.read_le().map_err(DecompressError::header)?;
+                let src_size: u32 = reader.read_le().map_err(DecompressError::header)?;
+                let _zero: u32 = reader.read_le().map_err(DecompressError::header)?;
+
+                let method = match flag {
+                    1 => CompressionMethod::Lzss,
+                    _ => CompressionMethod::Stored,
+                };
+
+                Ok(Self {
+                    method,
+                    header_size: 16,
+                    decompressed_size: dst_size as usize,
+                    compressed_size: Some(src_size as usize),
+                })
+            }
+            // the last header byte isn't understood yet
+            [0x30, len1, len2, _] => Ok(Self {
+                method: CompressionMethod::Rle,
+                header_size: 4,
+                decompressed_size: u16::from_le_bytes([len1, len2]) as usize,
+                compressed_size: None,
+            }),
+            _ => Err(DecompressError::UnknownMagic(magic)),
+        }
+    }
+}
+
+/// reads the compression header without decoding anything
+pub fn detect_compression(data: &[u8]) -> Option<CompressionInfo> {
+    let mut info = CompressionInfo::read(&mut &data[..]).ok()?;
+
+    if info.compressed_size.is_none() {
+        // the rle stream runs until the end of the data
+        info.compressed_size = Some(data.len() - info.header_size);
+    }
+
+    Some(info)
+}
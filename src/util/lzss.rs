@@ -1,47 +1,63 @@
-const WINDOW_SIZE: usize = 0x10000;
-const WINDOW_BASE: usize = 0xFEFD;
+use super::DecompressError;
 
-pub fn decompress(src: &[u8], dst_len: usize) -> Vec<u8> {
+pub(super) const WINDOW_SIZE: usize = 0x10000;
+pub(super) const WINDOW_BASE: usize = 0xFEFD;
+// the most output a single source byte can produce: a flag byte followed by
+// 8 matches of 3 bytes each expands to 8 * MAX_MATCH bytes
+const MAX_RATIO: usize = 8 * MAX_MATCH / 25 + 1;
+
+pub fn decompress(src: &[u8], dst_len: usize) -> Result<Vec<u8>, DecompressError> {
     let src_len = src.len();
-    let mut dst = vec![0; dst_len];
+    // don't trust dst_len for the allocation, a corrupted header could claim gigabytes
+    let mut dst = Vec::with_capacity(dst_len.min(src_len.saturating_mul(MAX_RATIO)));
     let mut win = [0; WINDOW_SIZE];
     let mut win_pos = WINDOW_BASE;
     let mut dst_pos = 0;
     let mut src_pos = 0;
 
-    while dst_pos < dst_len && src_pos < src_len {
-        let flags = src[src_pos];
+    let truncated = |actual| DecompressError::TruncatedStream {
+        expected: dst_len,
+        actual,
+    };
+
+    while dst_pos < dst_len {
+        let Some(&flags) = src.get(src_pos) else {
+            return Err(truncated(dst_pos));
+        };
         src_pos += 1;
 
         for shift in 0..8 {
+            if dst_pos >= dst_len {
+                break;
+            }
+
             // if flags & (1 << shift) != 0 {
             if (flags >> shift) & 1 == 1 {
-                let byte = src[src_pos];
+                let Some(&byte) = src.get(src_pos) else {
+                    return Err(truncated(dst_pos));
+                };
 
                 win[win_pos] = byte;
-                dst[dst_pos] = byte;
+                dst.push(byte);
 
                 dst_pos += 1;
                 src_pos += 1;
                 win_pos = (win_pos + 1) % WINDOW_SIZE;
             } else {
-                if src_pos > src_len - 3 {
-                    return dst;
+                if src_pos + 3 > src_len {
+                    return Err(truncated(dst_pos));
                 }
 
                 let mut offset = u16::from_le_bytes([src[src_pos], src[src_pos + 1]]) as usize;
                 let length = 4 + src[src_pos + 2] as usize;
                 src_pos += 3;
 
-                for _ in 0..length {
-                    if dst_pos >= dst_len {
-                        break;
-                    }
-
+                // a match running past the end is cut off
+                for _ in 0..length.min(dst_len - dst_pos) {
                     let byte = win[offset];
 
                     win[win_pos] = byte;
-                    dst[dst_pos] = byte;
+                    dst.push(byte);
 
                     offset = (offset + 1) % WINDOW_SIZE;
                     win_pos = (win_pos + 1) % WINDOW_SIZE;
@@ -51,5 +67,171 @@ pub fn decompress(src: &[u8], dst_len: usize) -> Vec<u8> {
         }
     }
 
+    Ok(dst)
+}
+
+const MIN_MATCH: usize = 4;
+const MAX_MATCH: usize = MIN_MATCH + 0xFF;
+const HASH_BITS: u32 = 14;
+const MAX_CHAIN: usize = 256;
+const NIL: usize = usize::MAX;
+
+pub fn compress(src: &[u8]) -> Vec<u8> {
+    // the window starts out zeroed, so pretend the input is preceded by zeros
+    // to allow runs of zero bytes at the beginning to be encoded as matches
+    let mut buf = vec![0; MAX_MATCH];
+    buf.extend_from_slice(src);
+
+    let mut dst = Vec::with_capacity(src.len() + src.len() / 8 + 1);
+    let mut chains = HashChains::new(buf.len());
+
+    for pos in 0..MAX_MATCH {
+        chains.insert(&buf, pos);
+    }
+
+    let mut pos = MAX_MATCH;
+    let mut flags_pos = 0;
+    let mut shift = 8;
+
+    while pos < buf.len() {
+        if shift == 8 {
+            flags_pos = dst.len();
+            dst.push(0);
+            shift = 0;
+        }
+
+        let (match_pos, match_len) = chains.find_match(&buf, pos);
+
+        if match_len >= MIN_MATCH {
+            // offsets are absolute positions in the window, not distances
+            let offset = (WINDOW_BASE + WINDOW_SIZE + match_pos - MAX_MATCH) % WINDOW_SIZE;
+            dst.extend((offset as u16).to_le_bytes());
+            dst.push((match_len - MIN_MATCH) as u8);
+
+            for _ in 0..match_len {
+                chains.insert(&buf, pos);
+                pos += 1;
+            }
+        } else {
+            dst[flags_pos] |= 1 << shift;
+            dst.push(buf[pos]);
+
+            chains.insert(&buf, pos);
+            pos += 1;
+        }
+
+        shift += 1;
+    }
+
     dst
 }
+
+struct HashChains {
+    head: Vec<usize>,
+    prev: Vec<usize>,
+}
+
+impl HashChains {
+    fn new(len: usize) -> Self {
+        Self {
+            head: vec![NIL; 1 << HASH_BITS],
+            prev: vec![NIL; len],
+        }
+    }
+
+    fn hash(buf: &[u8], pos: usize) -> usize {
+        let word = u32::from_le_bytes(buf[pos..][..MIN_MATCH].try_into().unwrap());
+        (word.wrapping_mul(0x9E3779B1) >> (32 - HASH_BITS)) as usize
+    }
+
+    fn insert(&mut self, buf: &[u8], pos: usize) {
+        if pos + MIN_MATCH <= buf.len() {
+            let hash = Self::hash(buf, pos);
+            self.prev[pos] = self.head[hash];
+            self.head[hash] = pos;
+        }
+    }
+
+    fn find_match(&self, buf: &[u8], pos: usize) -> (usize, usize) {
+        let max_len = (buf.len() - pos).min(MAX_MATCH);
+        let mut best = (0, 0);
+
+        if max_len < MIN_MATCH {
+            return best;
+        }
+
+        let mut candidate = self.head[Self::hash(buf, pos)];
+
+        for _ in 0..MAX_CHAIN {
+            // anything further back than the window size has been overwritten
+            if candidate == NIL || pos - candidate >= WINDOW_SIZE {
+                break;
+            }
+
+            // the decoder copies byte by byte, so a match may overlap itself
+            let len = (0..max_len)
+                .take_while(|&i| buf[candidate + i] == buf[pos + i])
+                .count();
+
+            if len > best.1 {
+                best = (candidate, len);
+
+                if len == max_len {
+                    break;
+                }
+            }
+
+            candidate = self.prev[candidate];
+        }
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WINDOW_SIZE, compress, decompress};
+
+    fn round_trip(data: &[u8]) {
+        let compressed = compress(data);
+        assert_eq!(decompress(&compressed, data.len()).unwrap(), data);
+    }
+
+    #[test]
+    fn last_match_is_clipped() {
+        // one match of 4 zero bytes from the window when only 2 are declared
+        assert_eq!(decompress(&[0x00, 0x00, 0x00, 0x00], 2).unwrap(), [0, 0]);
+    }
+
+    #[test]
+    fn empty() {
+        round_trip(&[]);
+    }
+
+    #[test]
+    fn leading_zeros() {
+        // matches into the zeroed window before any output
+        let mut data = vec![0; 300];
+        data.extend(b"after the zeros");
+        round_trip(&data);
+
+        assert!(compress(&data).len() < 32);
+    }
+
+    #[test]
+    fn window_wrap() {
+        let data = (0..WINDOW_SIZE * 3 + 123)
+            .map(|i| (i * 7 % 251) as u8 ^ (i >> 10) as u8)
+            .collect::<Vec<_>>();
+        round_trip(&data);
+    }
+
+    #[test]
+    fn overlapping_matches() {
+        let mut data = b"ab".repeat(200);
+        data.extend(b"xyz".repeat(100));
+        round_trip(&data);
+
+        assert!(compress(&data).len() < 32);
+    }
+}
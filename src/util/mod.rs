@@ -1,54 +1,125 @@
-use std::io::{Error, Read, Result};
+use std::io::{Error, Read, Result, Write};
 
 mod color;
-pub use color::Color;
+pub use color::{Color, Expansion, Transparency};
+mod error;
+pub use error::DecompressError;
+mod info;
+pub use info::{CompressionInfo, CompressionMethod, detect_compression};
 mod io;
 pub use io::{ReadEndian, ReadExt, WriteExt};
 mod lzss;
-pub use lzss::decompress as lzss_decompress;
-
-pub fn decompress<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
-    let magic = reader.read_bytes::<4>()?;
-
-    match &magic {
-        [0x12, 0x3D, 0xDA, flag @ 0..=1] => {
-            let dst_size: u32 = reader.read_le()?;
-            let src_size: u32 = reader.read_le()?;
-            let _zero: u32 = reader.read_le()?;
-
-            if *flag == 1 {
-                let mut src_data = vec![0; src_size as usize];
-                reader.read_exact(&mut src_data)?;
-                let data = lzss_decompress(&src_data, dst_size as usize);
-                Ok(data)
-            } else {
-                let mut data = vec![0; dst_size as usize];
-                reader.read_exact(&mut data)?;
-                Ok(data)
-            }
+pub use lzss::{compress as lzss_compress, decompress as lzss_decompress};
+mod reader;
+pub use reader::DecompressReader;
+mod rle;
+pub use rle::compress as rle_compress;
+
+pub fn decompress<R: Read>(reader: &mut R) -> std::result::Result<Vec<u8>, DecompressError> {
+    let info = CompressionInfo::read(reader)?;
+    let dst_size = info.decompressed_size;
+
+    match info.method {
+        CompressionMethod::Lzss => {
+            let src_size = info.compressed_size.unwrap_or_default();
+            let src_data = read_sized(reader, src_size)?;
+            lzss_decompress(&src_data, dst_size)
         }
-        [0x30, len1, len2, _] => {
-            let output_len = u16::from_le_bytes([*len1, *len2]);
-            let mut output = Vec::with_capacity(output_len as usize);
-
-            while let Ok(ctrl) = reader.read_le::<u8>() {
-                let flag = (ctrl & 0x80) != 0;
-                let mut len = (ctrl & 0x7F) as usize;
-
-                if flag {
-                    len += 3;
-                    let byte: u8 = reader.read_le()?;
-                    output.extend(std::iter::repeat_n(byte, len));
-                } else {
-                    len += 1;
-                    let current_len = output.len();
-                    output.resize(current_len + len, 0);
-                    reader.read_exact(&mut output[current_len..])?;
-                }
+        CompressionMethod::Stored => read_sized(reader, dst_size),
+        CompressionMethod::Rle => {
+            let mut output = Vec::with_capacity(dst_size);
+
+            // anything after the declared size is padding
+            while output.len() < dst_size {
+                let current_len = output.len();
+                rle::decompress_block(reader, &mut output, dst_size - current_len)
+                    .map_err(|err| DecompressError::stream(err, dst_size, current_len))?;
             }
 
             Ok(output)
         }
-        _ => Err(Error::other("uncompressed or unknown compression method")),
+    }
+}
+
+// reads exactly len bytes, growing the buffer as data arrives instead of
+// allocating whatever size a possibly corrupted header declares
+fn read_sized<R: Read>(
+    reader: &mut R,
+    len: usize,
+) -> std::result::Result<Vec<u8>, DecompressError> {
+    let mut data = Vec::new();
+    if let Err(err) = reader.take(len as u64).read_to_end(&mut data) {
+        return Err(DecompressError::stream(err, len, data.len()));
+    }
+
+    if data.len() != len {
+        return Err(DecompressError::TruncatedStream {
+            expected: len,
+            actual: data.len(),
+        });
+    }
+
+    Ok(data)
+}
+
+/// compresses `data` and writes it with the 16 byte lzss header
+pub fn write_lzss<W: Write>(writer: &mut W, data: &[u8]) -> Result<()> {
+    let Ok(dst_size) = u32::try_from(data.len()) else {
+        return Err(Error::other("data too large to compress"));
+    };
+
+    let compressed = lzss_compress(data);
+
+    writer.write_bytes([0x12, 0x3D, 0xDA, 0x01])?;
+    writer.write_le(dst_size)?;
+    writer.write_le(compressed.len() as u32)?;
+    writer.write_le(0u32)?;
+    writer.write_bytes(compressed)
+}
+
+/// writes `data` uncompressed with the 16 byte lzss header, as stored files are
+pub fn write_stored<W: Write>(writer: &mut W, data: &[u8]) -> Result<()> {
+    let Ok(size) = u32::try_from(data.len()) else {
+        return Err(Error::other("data too large to store"));
+    };
+
+    writer.write_bytes([0x12, 0x3D, 0xDA, 0x00])?;
+    writer.write_le(size)?;
+    writer.write_le(size)?;
+    writer.write_le(0u32)?;
+    writer.write_bytes(data)
+}
+
+/// compresses `data` and writes it with the 4 byte rle header
+pub fn write_rle<W: Write>(writer: &mut W, data: &[u8]) -> Result<()> {
+    let Ok(dst_size) = u16::try_from(data.len()) else {
+        return Err(Error::other("data too large to compress"));
+    };
+
+    let [len1, len2] = dst_size.to_le_bytes();
+    let compressed = rle_compress(data);
+
+    // the last header byte isn't understood yet, so it is left zero
+    writer.write_bytes([0x30, len1, len2, 0])?;
+    writer.write_bytes(compressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecompressError, decompress};
+
+    #[test]
+    fn truncated_stored_data() {
+        // stored header declaring 8 bytes, followed by only 3
+        let data = [
+            0x12, 0x3D, 0xDA, 0x00, 8, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3,
+        ];
+        assert!(matches!(
+            decompress(&mut &data[..]),
+            Err(DecompressError::TruncatedStream {
+                expected: 8,
+                actual: 3
+            })
+        ));
     }
 }
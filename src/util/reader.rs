@@ -0,0 +1,404 @@
+use super::{
+    CompressionInfo, CompressionMethod, DecompressError, ReadExt,
+    lzss::{WINDOW_BASE, WINDOW_SIZE},
+    rle,
+};
+use std::io::{self, Read};
+
+enum State {
+    Raw {
+        magic: [u8; 4],
+        magic_len: usize,
+        magic_pos: usize,
+    },
+    Stored,
+    Lzss {
+        window: Box<[u8]>,
+        win_pos: usize,
+        flags: u8,
+        shift: u8,
+        copy_offset: usize,
+        copy_len: usize,
+        src_remaining: usize,
+    },
+    Rle {
+        block: Vec<u8>,
+        block_pos: usize,
+    },
+}
+
+/// decompresses data on the fly while reading from the inner reader
+pub struct DecompressReader<R> {
+    inner: R,
+    state: State,
+    size: Option<usize>,
+    produced: usize,
+}
+
+impl<R: Read> DecompressReader<R> {
+    pub fn new(mut inner: R) -> Result<Self, DecompressError> {
+        let magic = inner.read_bytes::<4>().map_err(DecompressError::header)?;
+        let (state, size) = read_header(&mut inner, magic)?;
+
+        Ok(Self {
+            inner,
+            state,
+            size: Some(size),
+            produced: 0,
+        })
+    }
+
+    /// like `new`, but data with unknown magic is passed through as is
+    pub fn with_fallback(mut inner: R) -> Result<Self, DecompressError> {
+        let mut magic = [0; 4];
+        let mut magic_len = 0;
+
+        while magic_len < magic.len() {
+            match inner.read(&mut magic[magic_len..]) {
+                Ok(0) => break,
+                Ok(n) => magic_len += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(DecompressError::Io(err)),
+            }
+        }
+
+        let header = match magic_len {
+            4 => read_header(&mut inner, magic),
+            _ => Err(DecompressError::UnknownMagic(magic)),
+        };
+
+        let (state, size) = match header {
+            Ok((state, size)) => (state, Some(size)),
+            Err(DecompressError::UnknownMagic(_)) => {
+                let state = State::Raw {
+                    magic,
+                    magic_len,
+                    magic_pos: 0,
+                };
+                (state, None)
+            }
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self {
+            inner,
+            state,
+            size,
+            produced: 0,
+        })
+    }
+
+    /// the decompressed size declared in the header, `None` for uncompressed data
+    pub fn size(&self) -> Option<usize> {
+        self.size
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn truncated(&self) -> io::Error {
+        DecompressError::TruncatedStream {
+            expected: self.size.unwrap_or_default(),
+            actual: self.produced,
+        }
+        .into()
+    }
+
+    fn read_src(&mut self) -> io::Result<u8> {
+        if let State::Lzss { src_remaining, .. } = &mut self.state {
+            if *src_remaining == 0 {
+                return Err(self.truncated());
+            }
+            *src_remaining -= 1;
+        }
+
+        match self.inner.read_le::<u8>() {
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Err(self.truncated()),
+            other => other,
+        }
+    }
+
+    fn next_lzss(&mut self, remaining: usize) -> io::Result<u8> {
+        loop {
+            let State::Lzss {
+                window,
+                win_pos,
+                flags,
+                shift,
+                copy_offset,
+                copy_len,
+                ..
+            } = &mut self.state
+            else {
+                unreachable!()
+            };
+
+            if *copy_len > 0 {
+                let byte = window[*copy_offset];
+                window[*win_pos] = byte;
+
+                *copy_offset = (*copy_offset + 1) % WINDOW_SIZE;
+                *win_pos = (*win_pos + 1) % WINDOW_SIZE;
+                *copy_len -= 1;
+                return Ok(byte);
+            }
+
+            if *shift == 8 {
+                self.set_lzss_flags()?;
+                continue;
+            }
+
+            let literal = (*flags >> *shift) & 1 == 1;
+            *shift += 1;
+
+            if literal {
+                let byte = self.read_src()?;
+                let State::Lzss {
+                    window, win_pos, ..
+                } = &mut self.state
+                else {
+                    unreachable!()
+                };
+
+                window[*win_pos] = byte;
+                *win_pos = (*win_pos + 1) % WINDOW_SIZE;
+                return Ok(byte);
+            }
+
+            let offset = u16::from_le_bytes([self.read_src()?, self.read_src()?]) as usize;
+            let length = 4 + self.read_src()? as usize;
+
+            if let State::Lzss {
+                copy_offset,
+                copy_len,
+                ..
+            } = &mut self.state
+            {
+                // a match running past the end is cut off
+                *copy_offset = offset;
+                *copy_len = length.min(remaining);
+            }
+        }
+    }
+
+    // skips the rest of the declared source, which has to be there even when
+    // the output ends before it, as `decompress` reads all of it up front
+    fn finish_lzss(&mut self) -> io::Result<()> {
+        let State::Lzss { src_remaining, .. } = &mut self.state else {
+            unreachable!()
+        };
+
+        let skipped = io::copy(
+            &mut (&mut self.inner).take(*src_remaining as u64),
+            &mut io::sink(),
+        )?;
+        *src_remaining -= skipped as usize;
+
+        if *src_remaining > 0 {
+            return Err(self.truncated());
+        }
+        Ok(())
+    }
+
+    fn set_lzss_flags(&mut self) -> io::Result<()> {
+        let byte = self.read_src()?;
+
+        if let State::Lzss { flags, shift, .. } = &mut self.state {
+            *flags = byte;
+            *shift = 0;
+        }
+
+        Ok(())
+    }
+
+    fn next_rle(&mut self, remaining: usize) -> io::Result<u8> {
+        let State::Rle { block, block_pos } = &mut self.state else {
+            unreachable!()
+        };
+
+        if *block_pos == block.len() {
+            block.clear();
+            *block_pos = 0;
+
+            if let Err(err) = rle::decompress_block(&mut self.inner, block, remaining) {
+                return match err.kind() {
+                    io::ErrorKind::UnexpectedEof => Err(self.truncated()),
+                    _ => Err(err),
+                };
+            }
+        }
+
+        let State::Rle { block, block_pos } = &mut self.state else {
+            unreachable!()
+        };
+        *block_pos += 1;
+        Ok(block[*block_pos - 1])
+    }
+}
+
+impl<R: Read> Read for DecompressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = match self.size {
+            Some(size) => size - self.produced,
+            None => usize::MAX,
+        };
+        let len = buf.len().min(remaining);
+
+        let n = match &mut self.state {
+            State::Raw {
+                magic,
+                magic_len,
+                magic_pos,
+            } => {
+                if magic_pos < magic_len {
+                    let n = (*magic_len - *magic_pos).min(len);
+                    buf[..n].copy_from_slice(&magic[*magic_pos..][..n]);
+                    *magic_pos += n;
+                    n
+                } else {
+                    self.inner.read(&mut buf[..len])?
+                }
+            }
+            State::Stored => {
+                let n = self.inner.read(&mut buf[..len])?;
+                if n == 0 && len > 0 {
+                    return Err(self.truncated());
+                }
+                n
+            }
+            State::Lzss { .. } => {
+                for (i, byte) in buf[..len].iter_mut().enumerate() {
+                    *byte = self.next_lzss(remaining - i)?;
+                    self.produced += 1;
+                }
+                if len == remaining {
+                    self.finish_lzss()?;
+                }
+                return Ok(len);
+            }
+            State::Rle { .. } => {
+                for (i, byte) in buf[..len].iter_mut().enumerate() {
+                    *byte = self.next_rle(remaining - i)?;
+                    self.produced += 1;
+                }
+                return Ok(len);
+            }
+        };
+
+        self.produced += n;
+        Ok(n)
+    }
+}
+
+fn read_header<R: Read>(inner: &mut R, magic: [u8; 4]) -> Result<(State, usize), DecompressError> {
+    let info = CompressionInfo::from_magic(inner, magic)?;
+
+    let state = match info.method {
+        CompressionMethod::Stored => State::Stored,
+        CompressionMethod::Lzss => State::Lzss {
+            window: vec![0; WINDOW_SIZE].into_boxed_slice(),
+            win_pos: WINDOW_BASE,
+            flags: 0,
+            shift: 8,
+            copy_offset: 0,
+            copy_len: 0,
+            src_remaining: info.compressed_size.unwrap_or_default(),
+        },
+        CompressionMethod::Rle => State::Rle {
+            block: Vec::new(),
+            block_pos: 0,
+        },
+    };
+
+    Ok((state, info.decompressed_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DecompressReader;
+    use crate::util::{decompress, write_lzss, write_rle};
+    use std::io::{self, Read};
+
+    fn assert_same(data: &[u8]) {
+        let expected = decompress(&mut &data[..]).unwrap();
+
+        let mut output = Vec::new();
+        DecompressReader::new(data)
+            .unwrap()
+            .read_to_end(&mut output)
+            .unwrap();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn padded_rle() {
+        let data = [
+            0x30, 10, 0, 0x7F, 0x82, b'a', 0x04, b'b', b'c', b'd', b'e', b'f', 0, 0,
+        ];
+        assert_same(&data);
+    }
+
+    #[test]
+    fn clipped_lzss_match() {
+        let data = [
+            0x12, 0x3D, 0xDA, 0x01, 2, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        assert_same(&data);
+    }
+
+    #[test]
+    fn round_trips() {
+        let input = (0..5000).map(|i| (i / 7 % 13) as u8).collect::<Vec<_>>();
+
+        let mut data = Vec::new();
+        write_lzss(&mut data, &input).unwrap();
+        assert_same(&data);
+
+        data.clear();
+        write_rle(&mut data, &input).unwrap();
+        assert_same(&data);
+    }
+
+    #[test]
+    fn matches_decompress_on_random_input() {
+        // xorshift, so every run checks the same inputs
+        let mut state = 0x2545_F491u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as usize
+        };
+
+        for _ in 0..20000 {
+            // small declared sizes, so most inputs get past the header
+            let mut data = match next() % 3 {
+                0 => vec![0x12, 0x3D, 0xDA, 0x01],
+                1 => vec![0x12, 0x3D, 0xDA, 0x00],
+                _ => vec![0x30, (next() % 64) as u8, 0, 0],
+            };
+            if data[0] == 0x12 {
+                data.extend((next() as u32 % 64).to_le_bytes());
+                data.extend((next() as u32 % 64).to_le_bytes());
+                data.extend([0; 4]);
+            }
+            let len = next() % 64;
+            data.extend((0..len).map(|_| next() as u8));
+
+            let expected = decompress(&mut &data[..]);
+            let actual = DecompressReader::new(&data[..])
+                .map_err(io::Error::from)
+                .and_then(|mut reader| {
+                    let mut output = Vec::new();
+                    reader.read_to_end(&mut output).map(|_| output)
+                });
+
+            match (expected, actual) {
+                (Ok(expected), Ok(actual)) => assert_eq!(actual, expected, "{data:02X?}"),
+                (Err(_), Err(_)) => {}
+                (expected, actual) => panic!("{data:02X?}: {expected:?} but {actual:?}"),
+            }
+        }
+    }
+}
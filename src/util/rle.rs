@@ -0,0 +1,83 @@
+use super::ReadExt;
+use std::io::{Read, Result};
+
+const MIN_RUN: usize = 3;
+const MAX_RUN: usize = MIN_RUN + 0x7F;
+const MAX_LITERAL: usize = 1 + 0x7F;
+
+/// decodes the block at the next control byte into `output`, keeping at
+/// most `limit` bytes of it
+pub(super) fn decompress_block<R: Read>(
+    reader: &mut R,
+    output: &mut Vec<u8>,
+    limit: usize,
+) -> Result<()> {
+    let ctrl: u8 = reader.read_le()?;
+    let len = (ctrl & 0x7F) as usize;
+
+    if ctrl & 0x80 != 0 {
+        let byte: u8 = reader.read_le()?;
+        output.extend(std::iter::repeat_n(byte, (len + MIN_RUN).min(limit)));
+    } else {
+        let start = output.len();
+        output.resize(start + (len + 1).min(limit), 0);
+        reader.read_exact(&mut output[start..])?;
+    }
+
+    Ok(())
+}
+
+pub fn compress(src: &[u8]) -> Vec<u8> {
+    let src_len = src.len();
+
+    // run_len[i] is the number of times src[i] repeats starting at i
+    let mut run_len = vec![0; src_len];
+    for i in (0..src_len).rev() {
+        run_len[i] = match src.get(i + 1) {
+            Some(&next) if next == src[i] => run_len[i + 1] + 1,
+            _ => 1,
+        };
+    }
+
+    // cost[i] is the smallest encoded size of src[i..], step[i] the block that achieves it
+    let mut cost = vec![usize::MAX; src_len + 1];
+    let mut step = vec![(false, 0); src_len];
+    cost[src_len] = 0;
+
+    for i in (0..src_len).rev() {
+        for len in 1..=MAX_LITERAL.min(src_len - i) {
+            let total = 1 + len + cost[i + len];
+            if total < cost[i] {
+                cost[i] = total;
+                step[i] = (false, len);
+            }
+        }
+
+        for len in MIN_RUN..=MAX_RUN.min(run_len[i]) {
+            let total = 2 + cost[i + len];
+            if total < cost[i] {
+                cost[i] = total;
+                step[i] = (true, len);
+            }
+        }
+    }
+
+    let mut dst = Vec::with_capacity(cost[0]);
+    let mut pos = 0;
+
+    while pos < src_len {
+        let (repeat, len) = step[pos];
+
+        if repeat {
+            dst.push(0x80 | (len - MIN_RUN) as u8);
+            dst.push(src[pos]);
+        } else {
+            dst.push((len - 1) as u8);
+            dst.extend_from_slice(&src[pos..][..len]);
+        }
+
+        pos += len;
+    }
+
+    dst
+}
@@ -0,0 +1,419 @@
+use crate::util::{ReadExt, WriteExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Take, Write};
+
+pub const NAME_LEN: usize = 24;
+pub const RECORD_HEADER_LEN: usize = NAME_LEN + 8;
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// member order and layout of an unpacked archive, needed to rebuild it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub alignment: u32,
+    /// number of zero bytes after the last member
+    pub tail_len: u32,
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub name: String,
+    /// the byte preceding the name, its meaning is unknown
+    pub flag: u8,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub name_trailer: Vec<u8>,
+    /// file the member is unpacked to when an earlier member has the same name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+impl ManifestEntry {
+    /// name of the unpacked file holding the member data
+    pub fn file_name(&self) -> &str {
+        self.file.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub name: String,
+    /// the byte preceding the name, its meaning is unknown
+    pub flag: u8,
+    /// leftover bytes after the name terminator, without the trailing zeros
+    pub name_trailer: Vec<u8>,
+    pub size: u32,
+    /// absolute position of the member data
+    pub offset: u64,
+    /// absolute position of the next record
+    pub next: u32,
+}
+
+/// random access to the members of an archive
+pub struct Archive<R> {
+    reader: R,
+    entries: Vec<Entry>,
+    end: u64,
+    len: u64,
+}
+
+impl<R: Read + Seek> Archive<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        let mut entries = Vec::new();
+        let mut pos = 0;
+
+        while pos + NAME_LEN as u64 <= len {
+            reader.seek(SeekFrom::Start(pos))?;
+            let name_buf = reader.read_bytes::<NAME_LEN>()?;
+            let end = name_buf
+                .iter()
+                .position(|&b| b == 0)
+                .unwrap_or(name_buf.len());
+
+            if end == 0 {
+                break;
+            }
+
+            let Ok(name) = str::from_utf8(&name_buf[1..end]) else {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "member name isn't utf-8",
+                ));
+            };
+            let trailer = name_buf.get(end + 1..).unwrap_or_default();
+            let trailer_len = trailer
+                .iter()
+                .rposition(|&b| b != 0)
+                .map_or(0, |last| last + 1);
+            let size: u32 = reader.read_le()?;
+            let next: u32 = reader.read_le()?;
+            let offset = pos + RECORD_HEADER_LEN as u64;
+
+            if offset + size as u64 > len || next as u64 <= pos {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("bad record for {name}"),
+                ));
+            }
+
+            entries.push(Entry {
+                name: name.to_string(),
+                flag: name_buf[0],
+                name_trailer: trailer[..trailer_len].to_vec(),
+                size,
+                offset,
+                next,
+            });
+            pos = next as u64;
+        }
+
+        Ok(Self {
+            reader,
+            entries,
+            end: pos.min(len),
+            len,
+        })
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// reads a single member without touching the others, the first one
+    /// when several members have the same name
+    pub fn read(&mut self, name: &str) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.reader(name)?.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    pub fn reader(&mut self, name: &str) -> Result<Take<&mut R>> {
+        let Some(index) = self.entries.iter().position(|entry| entry.name == name) else {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("no member named {name}"),
+            ));
+        };
+        self.reader_at(index)
+    }
+
+    /// reads the member at `index` in [`Archive::entries`]
+    pub fn read_at(&mut self, index: usize) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.reader_at(index)?.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    pub fn reader_at(&mut self, index: usize) -> Result<Take<&mut R>> {
+        let Some(entry) = self.entries.get(index) else {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("no member at index {index}"),
+            ));
+        };
+        let (offset, size) = (entry.offset, entry.size as u64);
+
+        self.reader.seek(SeekFrom::Start(offset))?;
+        Ok(self.reader.by_ref().take(size))
+    }
+
+    pub fn manifest(&self) -> Manifest {
+        let records = self
+            .entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.offset as usize + entry.size as usize,
+                    entry.next as usize,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // duplicate names get a numbered file so no member overwrites another
+        // one, or the manifest unpacked next to them
+        let mut taken = HashSet::from([MANIFEST_NAME.to_string()]);
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let file = (!taken.insert(entry.name.clone())).then(|| {
+                    let file = (2..)
+                        .map(|n| format!("{}.{n}", entry.name))
+                        .find(|file| {
+                            !taken.contains(file)
+                                && !self.entries.iter().any(|entry| entry.name == *file)
+                        })
+                        .unwrap_or_default();
+                    taken.insert(file.clone());
+                    file
+                });
+
+                ManifestEntry {
+                    name: entry.name.clone(),
+                    flag: entry.flag,
+                    name_trailer: entry.name_trailer.clone(),
+                    file,
+                }
+            })
+            .collect();
+
+        Manifest {
+            alignment: infer_alignment(&records),
+            tail_len: (self.len - self.end) as u32,
+            entries,
+        }
+    }
+
+    /// packs the members again following the archive's own manifest
+    pub fn rebuild(&mut self) -> Result<Vec<u8>> {
+        let manifest = self.manifest();
+        let mut writer = Writer::new(Vec::new(), manifest.alignment);
+
+        for (entry, manifest_entry) in self.entries.clone().iter().zip(&manifest.entries) {
+            self.reader.seek(SeekFrom::Start(entry.offset))?;
+            let mut data = vec![0; entry.size as usize];
+            self.reader.read_exact(&mut data)?;
+            writer.add(manifest_entry, &data)?;
+        }
+
+        writer.finish(manifest.tail_len)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// writes archive records, each one pointing to the start of the next
+pub struct Writer<W> {
+    writer: W,
+    alignment: usize,
+    pos: usize,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(writer: W, alignment: u32) -> Self {
+        Self {
+            writer,
+            alignment: alignment.max(1) as usize,
+            pos: 0,
+        }
+    }
+
+    pub fn add(&mut self, entry: &ManifestEntry, data: &[u8]) -> Result<()> {
+        let name = &entry.name;
+        let name_end = 1 + name.len();
+        let trailer = &entry.name_trailer;
+
+        // the name sits between the flag byte and a null terminator, which
+        // can only be left out when the name fills the whole field
+        let fits = match name_end {
+            NAME_LEN => trailer.is_empty(),
+            _ => name_end + 1 + trailer.len() <= NAME_LEN,
+        };
+
+        if name.is_empty() || name.contains('\0') || !fits {
+            return Err(Error::other(format!("invalid member name {name:?}")));
+        }
+
+        if entry.flag == 0 {
+            return Err(Error::other("a zero flag marks the end of the archive"));
+        }
+
+        let data_end = self.pos + RECORD_HEADER_LEN + data.len();
+        let next = data_end.next_multiple_of(self.alignment);
+        let Ok(size) = u32::try_from(data.len()) else {
+            return Err(Error::other(format!("{name} is too large")));
+        };
+        let Ok(next_u32) = u32::try_from(next) else {
+            return Err(Error::other("archive is too large"));
+        };
+
+        let mut name_buf = [0; NAME_LEN];
+        name_buf[0] = entry.flag;
+        name_buf[1..name_end].copy_from_slice(name.as_bytes());
+        if name_end < NAME_LEN {
+            name_buf[name_end + 1..][..trailer.len()].copy_from_slice(trailer);
+        }
+
+        self.writer.write_bytes(name_buf)?;
+        self.writer.write_le(size)?;
+        self.writer.write_le(next_u32)?;
+        self.writer.write_bytes(data)?;
+        self.write_zeros(next - data_end)?;
+        self.pos = next;
+
+        Ok(())
+    }
+
+    pub fn finish(mut self, tail_len: u32) -> Result<W> {
+        self.write_zeros(tail_len as usize)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_zeros(&mut self, len: usize) -> Result<()> {
+        self.writer.write_bytes(vec![0; len])
+    }
+}
+
+/// the largest power of two up to 0x800 that every record could be aligned to
+pub fn infer_alignment(records: &[(usize, usize)]) -> u32 {
+    let mut alignment = 0x800;
+
+    while alignment > 1 {
+        let aligned = records
+            .iter()
+            .all(|&(data_end, next)| data_end.next_multiple_of(alignment) == next);
+
+        if aligned {
+            break;
+        }
+
+        alignment /= 2;
+    }
+
+    alignment as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Archive, MANIFEST_NAME, ManifestEntry, Writer, infer_alignment};
+    use std::io::Cursor;
+
+    fn entry(name: &str) -> ManifestEntry {
+        ManifestEntry {
+            name: name.to_string(),
+            flag: 1,
+            name_trailer: Vec::new(),
+            file: None,
+        }
+    }
+
+    fn pack(members: &[(ManifestEntry, &[u8])], alignment: u32, tail_len: u32) -> Vec<u8> {
+        let mut writer = Writer::new(Vec::new(), alignment);
+        for (entry, data) in members {
+            writer.add(entry, data).unwrap();
+        }
+        writer.finish(tail_len).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let trailed = ManifestEntry {
+            flag: 7,
+            name_trailer: vec![0xAB, 0, 0xCD],
+            ..entry("b.txt")
+        };
+        let members = [
+            (entry("a.bin"), &b"first member"[..]),
+            (trailed, b"second"),
+            (entry("c"), b""),
+        ];
+        let original = pack(&members, 0x40, 0x30);
+
+        let mut archive = Archive::new(Cursor::new(original.clone())).unwrap();
+        assert_eq!(archive.read("a.bin").unwrap(), b"first member");
+        assert_eq!(archive.read_at(1).unwrap(), b"second");
+        assert_eq!(archive.entries()[1].flag, 7);
+        assert_eq!(archive.entries()[1].name_trailer, [0xAB, 0, 0xCD]);
+        assert_eq!(archive.rebuild().unwrap(), original);
+    }
+
+    #[test]
+    fn alignment_and_tail_are_inferred() {
+        let members = [(entry("a"), &[1; 5][..]), (entry("b"), &[2; 100])];
+        let archive = Archive::new(Cursor::new(pack(&members, 0x80, 0x200))).unwrap();
+
+        let manifest = archive.manifest();
+        assert_eq!(manifest.alignment, 0x80);
+        assert_eq!(manifest.tail_len, 0x200);
+    }
+
+    #[test]
+    fn alignment_is_the_largest_that_fits_every_record() {
+        assert_eq!(infer_alignment(&[(0x21, 0x30), (0x55, 0x60)]), 0x10);
+        assert_eq!(infer_alignment(&[(0x21, 0x40), (0x55, 0x60)]), 0x20);
+        assert_eq!(infer_alignment(&[(0x21, 0x21)]), 1);
+        assert_eq!(infer_alignment(&[]), 0x800);
+    }
+
+    #[test]
+    fn duplicate_names() {
+        let members = [
+            (entry("a.bin"), &b"one"[..]),
+            (entry("a.bin"), b"two"),
+            (entry("a.bin.2"), b"three"),
+        ];
+        let original = pack(&members, 4, 0);
+        let mut archive = Archive::new(Cursor::new(original.clone())).unwrap();
+
+        // by name the first one is found, by index every one
+        assert_eq!(archive.read("a.bin").unwrap(), b"one");
+        assert_eq!(archive.read_at(1).unwrap(), b"two");
+
+        let files = archive
+            .manifest()
+            .entries
+            .iter()
+            .map(|entry| entry.file_name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(files, ["a.bin", "a.bin.3", "a.bin.2"]);
+        assert_eq!(archive.rebuild().unwrap(), original);
+    }
+
+    #[test]
+    fn member_named_like_the_manifest() {
+        let members = [(entry(MANIFEST_NAME), &b"{}"[..])];
+        let archive = Archive::new(Cursor::new(pack(&members, 4, 0))).unwrap();
+
+        let manifest = archive.manifest();
+        assert_eq!(manifest.entries[0].name, MANIFEST_NAME);
+        assert_eq!(manifest.entries[0].file_name(), "manifest.json.2");
+    }
+}